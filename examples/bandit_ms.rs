@@ -3,7 +3,7 @@ use caesars_palace_nes::*;
 fn main() {
     for index in 0..=0xFF {
         let mut rng = Rng::with_index(index);
-        let rs: [u8; 3] = std::array::from_fn(|_| rng.gen());
+        let rs: [u8; 3] = rng.gen_array();
 
         let prize = bandit_ms_play(rs);
 
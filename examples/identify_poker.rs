@@ -0,0 +1,21 @@
+use clap::Parser;
+
+use caesars_palace_nes::*;
+
+/// 初期状態の乱数テーブルを仮定し、配られたカードから乱数インデックスを特定する。
+#[derive(Debug, Parser)]
+struct Cli {
+    /// 配られた順のカード ("HA S10 CK D2 C3" など)。
+    #[arg(required = true)]
+    cards: Vec<Card>,
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    let rng = Rng::new();
+
+    for index in identify_poker_index(&rng, &cli.cards) {
+        println!("0x{index:02X}");
+    }
+}
@@ -0,0 +1,15 @@
+use caesars_palace_nes::*;
+
+/// 各スロットマシンの配当表を出力する。
+///
+/// 出力はゲーム画面の配置ではなく、倍率を見比べるための表 (`PayTable` の `Display` を参照)。
+fn main() {
+    let version = GameVersion::default();
+
+    for (i, machine) in BanditMachine::all().into_iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        print!("{}", version.bandit_pay_table(machine));
+    }
+}
@@ -52,7 +52,7 @@ fn solve_leaf(rng: &mut Rng, moves: &[Move]) {
 
 /// (ロイヤルフラッシュ可能か, 判定時に消費した乱数の個数) を返す。
 fn check(rng: &mut Rng) -> (bool, usize) {
-    const MASK_ROYAL: u32 = (1 << 9) | (1 << 10) | (1 << 11) | (1 << 12) | (1 << 0);
+    const MASK_ROYAL: u16 = (1 << 9) | (1 << 10) | (1 << 11) | (1 << 12) | (1 << 0);
 
     let mut deck = Deck::new();
     let mut len = 0;
@@ -70,16 +70,17 @@ fn check(rng: &mut Rng) -> (bool, usize) {
         }};
     }
 
-    // 5 枚引き、各スートの mask を得る。
-    let mut masks_cur = [0; 4];
+    // 5 枚引く。
+    let mut hand = CardSet::new();
     for _ in 0..5 {
-        let card = deal!();
-        let i = usize::from(card.suit().inner());
-        masks_cur[i] |= 1 << card.rank().inner();
+        hand.insert(deal!());
     }
 
     // いずれかのスートでロイヤルストレートが成立していたらOK。
-    if masks_cur.into_iter().any(|mask| mask == MASK_ROYAL) {
+    if CardSuit::all()
+        .into_iter()
+        .any(|suit| hand.rank_mask(suit) == MASK_ROYAL)
+    {
         ret!(true);
     }
 
@@ -94,8 +95,7 @@ fn check(rng: &mut Rng) -> (bool, usize) {
         }
 
         let suit = card.suit();
-        let i = usize::from(suit.inner());
-        let mask_cur = masks_cur[i];
+        let mask_cur = hand.rank_mask(suit);
 
         (suit, mask_cur, mask_new)
     };
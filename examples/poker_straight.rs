@@ -11,22 +11,22 @@ fn main() {
     }
 }
 
-fn masks_straight() -> impl Iterator<Item = u32> {
-    const MASK_ROYAL: u32 = (1 << 9) | (1 << 10) | (1 << 11) | (1 << 12) | (1 << 0);
+fn masks_straight() -> impl Iterator<Item = u16> {
+    const MASK_ROYAL: u16 = (1 << 9) | (1 << 10) | (1 << 11) | (1 << 12) | (1 << 0);
 
     (0..=8)
         .map(|shift| 0b11111 << shift)
         .chain(std::iter::once(MASK_ROYAL))
 }
 
-fn can_make_straight(mut rng: Rng, mask_straight: u32) -> bool {
+fn can_make_straight(mut rng: Rng, mask_straight: u16) -> bool {
     let mut deck = Deck::new();
 
-    let mut mask_cur = 0;
+    let mut hand = CardSet::new();
     for _ in 0..5 {
-        let card = deck.deal(rng.gen());
-        mask_cur |= 1 << card.rank().inner();
+        hand.insert(deck.deal(rng.gen()));
     }
+    let mask_cur = hand.rank_mask_any();
 
     if mask_cur == mask_straight {
         return true;
@@ -0,0 +1,57 @@
+use clap::Parser;
+
+use caesars_palace_nes::*;
+
+/// 各スロットマシンの還元率、当たりの頻度、賞金の分散と標準偏差を、BET 単位と BET 枚数ごとに出力する。
+///
+/// 値は一様な乱数列を仮定した厳密な分布から求める。
+/// ポーカーは配当表と引き直しの規則がまだ本クレートにないため未対応で、その旨を出力する。
+#[derive(Debug, Parser)]
+struct Cli {
+    /// "Fountains of Fortune" の BET 単位。
+    #[arg(long, value_delimiter = ',', default_value = "1,5")]
+    fof_units: Vec<u32>,
+
+    /// "Magnificent Sevens" の BET 単位。
+    #[arg(long, value_delimiter = ',', default_value = "100")]
+    ms_units: Vec<u32>,
+
+    /// "Riches of Rome" の BET 単位。
+    #[arg(long, value_delimiter = ',', default_value = "500")]
+    ror_units: Vec<u32>,
+}
+
+fn main() -> Result<(), BetError> {
+    let cli = Cli::parse();
+
+    println!("machine\tunit\tcount\tbet\trtp\thit\tvariance\tstddev");
+    print_machine("fof", &bandit_fof_distribution(), &cli.fof_units)?;
+    print_machine("ms", &bandit_ms_distribution(), &cli.ms_units)?;
+    print_machine("ror", &bandit_ror_distribution(), &cli.ror_units)?;
+    println!("# poker: not reported (pay table and draw rules are not known yet)");
+
+    Ok(())
+}
+
+fn print_machine<P: BanditPrize>(
+    name: &str,
+    dist: &PrizeDistribution<P>,
+    bet_units: &[u32],
+) -> Result<(), BetError> {
+    for &bet_unit in bet_units {
+        for bet_count in 1..=P::MACHINE.bet_count_max() {
+            let bet = Bet::new(P::MACHINE, Money::new(bet_unit), bet_count)?;
+            let variance = dist.variance(bet)?;
+            println!(
+                "{name}\t{bet_unit}\t{bet_count}\t{}\t{:.4}\t{:.4}\t{:.1}\t{:.1}",
+                bet.total()?,
+                dist.rtp(bet)?,
+                dist.hit_frequency(bet)?,
+                variance,
+                variance.sqrt()
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use clap::Parser;
+
+use caesars_palace_nes::*;
+
+/// 初期状態から乱数を長時間生成し、状態の周期とインデックスごとの統計を出力する。
+#[derive(Debug, Parser)]
+struct Cli {
+    /// 総消費回数。
+    #[arg(long, default_value_t = 1_000_000_000, value_parser = parse_int::parse::<u64>)]
+    draws: u64,
+
+    /// 統計を集計する局面の数 (総消費回数を等分する)。
+    #[arg(long, default_value_t = 1)]
+    phases: u64,
+
+    /// 開始インデックス。
+    #[arg(long, default_value_t = 0, value_parser = parse_int::parse::<u8>)]
+    index: u8,
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    let mut evo = RngEvolution::new(Rng::with_index(cli.index));
+
+    let phase_len = cli.draws / cli.phases.max(1);
+    for phase in 0..cli.phases.max(1) {
+        let start = evo.draws();
+        evo.reset_stats();
+        evo.run(phase_len);
+
+        println!("# phase {phase}: draws {start}..{}", evo.draws());
+        println!("index\tcount\tchi2\tmax_bias\tcorr_next");
+        for (index, stats) in evo.index_stats().iter().enumerate() {
+            let format = |value: Option<f64>, precision: usize, sign: bool| match value {
+                Some(value) if sign => format!("{value:+.precision$}"),
+                Some(value) => format!("{value:.precision$}"),
+                None => "-".to_owned(),
+            };
+            println!(
+                "{index}\t{}\t{}\t{}\t{}",
+                stats.count(),
+                format(stats.chi_square(), 1, false),
+                format(stats.max_bias(), 4, false),
+                format(stats.correlation_next(), 4, true)
+            );
+        }
+    }
+
+    match evo.period() {
+        Some(period) => println!("# period: {period}"),
+        None => println!("# no repeat within {} draws", evo.draws()),
+    }
+}
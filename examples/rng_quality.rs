@@ -0,0 +1,42 @@
+use clap::Parser;
+
+use caesars_palace_nes::*;
+
+/// 各ゲームでの乱数の縮約について統計的検定を行い、結果を出力する。
+#[derive(Debug, Parser)]
+struct Cli {
+    /// 各開始インデックスから生成する乱数の個数。
+    #[arg(long, default_value_t = 10000)]
+    len: usize,
+
+    /// 局面の数。
+    #[arg(long, default_value_t = 4)]
+    phases: usize,
+
+    /// 局面の間の消費回数。
+    #[arg(long, default_value_t = 1_000_000, value_parser = parse_int::parse::<u64>)]
+    phase_gap: u64,
+
+    /// 全ての結果を CSV で出力する。
+    #[arg(long)]
+    csv: bool,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+
+    let params = QualityParams {
+        len: cli.len,
+        phases: cli.phases,
+        phase_gap: cli.phase_gap,
+    };
+    let report = QualityReport::generate(&Rng::new(), &RngReduction::GAMES, params)?;
+
+    if cli.csv {
+        print!("{}", report.to_csv());
+    } else {
+        print!("{report}");
+    }
+
+    Ok(())
+}
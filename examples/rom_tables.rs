@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use caesars_palace_nes::*;
+
+/// iNES ファイルの情報と、本クレートが持つテーブルとの差分を表示する。
+#[derive(Debug, Parser)]
+struct Cli {
+    path: PathBuf,
+
+    /// テーブルの配置ファイル。既知のダンプでない ROM を照合する場合に指定する。
+    #[arg(long)]
+    layout: Option<PathBuf>,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+
+    let rom = Rom::load(&cli.path)?;
+
+    println!("mapper:\t{}", rom.mapper());
+    println!("PRG:\t0x{:X} bytes", rom.prg().len());
+    println!("CHR:\t0x{:X} bytes", rom.chr().len());
+    println!("CRC32:\t{:08X}", rom.crc32());
+
+    let diffs = match &cli.layout {
+        Some(path) => {
+            let layout: RomLayout = std::fs::read_to_string(path)?.parse()?;
+            rom.verify_tables_at(&layout)?
+        }
+        None => {
+            let dump = rom.known_dump().ok_or(RomError::UnknownDump(rom.crc32()))?;
+            println!("dump:\t{}", dump.name);
+            rom.verify_tables()?
+        }
+    };
+    if diffs.is_empty() {
+        println!("all tables match");
+    }
+    for diff in diffs {
+        println!("{diff}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use caesars_palace_nes::*;
+
+/// ステートセーブファイルからゲームの状態を読み取り、現在の乱数インデックスでの結果を予測する。
+#[derive(Debug, Parser)]
+struct Cli {
+    /// ステートセーブファイル (FCEUX `.fcs` または Mesen `.mss`)。
+    path: PathBuf,
+
+    /// RAM 配置ファイル。省略時はゲーム本体の配置を使う。
+    #[arg(long)]
+    ram_map: Option<PathBuf>,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+
+    let map: RamMap = match &cli.ram_map {
+        Some(path) => std::fs::read_to_string(path)?.parse()?,
+        None => RamMap::game().ok_or("the game's RAM map is not known yet; pass --ram-map")?,
+    };
+
+    let bytes = std::fs::read(&cli.path)?;
+    let snapshot = GameSnapshot::from_savestate(&bytes, &map)?;
+
+    println!("rng index:\t0x{:02X}", snapshot.rng.index());
+    println!("money:\t{}", snapshot.money);
+    println!(
+        "jackpot:\t{}\t{}",
+        snapshot.jackpot_small, snapshot.jackpot_big
+    );
+    println!("location:\t0x{:02X}", snapshot.location);
+
+    {
+        let mut rng = snapshot.rng.clone();
+        let mut deck = Deck::new();
+        let cards: Vec<_> = (0..5).map(|_| deck.deal(rng.gen()).to_string()).collect();
+        println!("poker:\t{}", cards.join(" "));
+    }
+    {
+        let mut rng = snapshot.rng.clone();
+        let rs = rng.gen_array();
+        println!("fof:\t{:?}", bandit_fof_play(rs));
+    }
+    {
+        let mut rng = snapshot.rng.clone();
+        let rs = rng.gen_array();
+        println!("ms:\t{:?}", bandit_ms_play(rs).factors());
+    }
+    {
+        let mut rng = snapshot.rng.clone();
+        let rs = rng.gen_array();
+        println!("ror:\t{:?}", bandit_ror_play(rs).factors());
+    }
+
+    Ok(())
+}
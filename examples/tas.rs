@@ -5,16 +5,16 @@ use arrayvec::ArrayVec;
 
 use caesars_palace_nes::*;
 
-const BET_UNIT_BANDIT_FOF: u32 = 1; // $5 を使っても大ジャックポット以外は大差ないので...
-const BET_UNIT_BANDIT_MS: u32 = 100;
-const BET_UNIT_BANDIT_ROR: u32 = 500;
+const BET_UNIT_BANDIT_FOF: Money = Money::new(1); // $5 を使っても大ジャックポット以外は大差ないので...
+const BET_UNIT_BANDIT_MS: Money = Money::new(100);
+const BET_UNIT_BANDIT_ROR: Money = Money::new(500);
 
 // $100 を 2 回賭けしたロイヤルフラッシュで 50000 稼げるので一応。
 // 多分ロイヤルフラッシュ以外は考慮しなくていいと思う。
-const COST_POKER: u32 = 200;
+const COST_POKER: Money = Money::new(200);
 
 fn main() {
-    const MONEY_TARGET: u32 = 0x20000;
+    const MONEY_TARGET: Money = Money::new(0x20000);
     const DEPTH: u32 = 2;
 
     let mut state = State::new();
@@ -23,7 +23,7 @@ fn main() {
     solve(&mut state, &mut cmoves, MONEY_TARGET, DEPTH);
 }
 
-fn solve(state: &mut State, cmoves: &mut ConcreteMoves, money_target: u32, depth_remain: u32) {
+fn solve(state: &mut State, cmoves: &mut ConcreteMoves, money_target: Money, depth_remain: u32) {
     if depth_remain == 0 {
         solve_leaf(state, cmoves, money_target);
         return;
@@ -42,9 +42,13 @@ fn solve(state: &mut State, cmoves: &mut ConcreteMoves, money_target: u32, depth
     }
 }
 
-fn solve_leaf(state: &mut State, cmoves: &mut ConcreteMoves, money_target: u32) {
+fn solve_leaf(state: &mut State, cmoves: &mut ConcreteMoves, money_target: Money) {
     // 枝刈り。大ジャックポットは時間経過で増えるので、少し余裕を見ておく。
-    if state.money() + 100050 < money_target {
+    if state
+        .money()
+        .checked_add(Money::new(100050))
+        .is_ok_and(|money| money < money_target)
+    {
         return;
     }
 
@@ -66,14 +70,17 @@ fn solve_leaf(state: &mut State, cmoves: &mut ConcreteMoves, money_target: u32)
 /// 局面。
 #[derive(Debug)]
 struct State {
-    money: u32,
+    money: Money,
+    /// 所持金の上限。ゲームの上限が確認できていればそれを、そうでなければ上限なしとして扱う。
+    money_limit: MoneyLimit,
     rng: Rng,
 }
 
 impl Default for State {
     fn default() -> Self {
         Self {
-            money: 1000,
+            money: Money::new(1000),
+            money_limit: MoneyLimit::game().unwrap_or_default(),
             rng: Rng::new(),
         }
     }
@@ -84,7 +91,7 @@ impl State {
         Self::default()
     }
 
-    fn money(&self) -> u32 {
+    fn money(&self) -> Money {
         self.money
     }
 
@@ -95,19 +102,19 @@ impl State {
         let mut moves = Moves::new();
 
         {
-            let bet_count_max = 3.min(self.money / BET_UNIT_BANDIT_FOF) as usize;
+            let bet_count_max = 3.min(self.money.get() / BET_UNIT_BANDIT_FOF.get()) as usize;
             if let Some(bet_count_max) = NonZeroUsize::new(bet_count_max) {
                 moves.push(Move::BanditFof { bet_count_max });
             }
         }
         {
-            let bet_count_max = 3.min(self.money / BET_UNIT_BANDIT_MS) as usize;
+            let bet_count_max = 3.min(self.money.get() / BET_UNIT_BANDIT_MS.get()) as usize;
             if let Some(bet_count_max) = NonZeroUsize::new(bet_count_max) {
                 moves.push(Move::BanditMs { bet_count_max });
             }
         }
         {
-            let bet_count_max = 3.min(self.money / BET_UNIT_BANDIT_ROR) as usize;
+            let bet_count_max = 3.min(self.money.get() / BET_UNIT_BANDIT_ROR.get()) as usize;
             if let Some(bet_count_max) = NonZeroUsize::new(bet_count_max) {
                 moves.push(Move::BanditRor { bet_count_max });
             }
@@ -139,28 +146,32 @@ impl State {
         bet_count_max: NonZeroUsize,
         rng_index: u8,
     ) -> (ConcreteMove, UndoInfo) {
-        const BET_UNIT: u32 = BET_UNIT_BANDIT_FOF;
+        const MACHINE: BanditMachine = BanditMachine::Fof;
+        const BET_UNIT: Money = BET_UNIT_BANDIT_FOF;
 
-        debug_assert!(self.money >= BET_UNIT * bet_count_max.get() as u32);
+        debug_assert!(self.money >= BET_UNIT.checked_mul(bet_count_max.get() as u32).unwrap());
 
         let rng_index_before = self.rng.index();
         self.rng.set_index(rng_index);
 
-        let rs = rand_4(&mut self.rng);
+        let rs = self.rng.gen_array();
         let prize = bandit_fof_play(rs);
 
-        // 収入が最大のものを選ぶ。同点なら BET 枚数が最小のものを選ぶ。
-        let (bet_count, income) = bet_counts(bet_count_max)
-            .map(|bet_count| {
-                let gain = prize.calc(BET_UNIT, bet_count) as i32;
-                let loss = (BET_UNIT * bet_count.get() as u32) as i32;
-                let income = gain - loss;
-                (bet_count, income)
+        // 収支が最大のものを選ぶ。同点なら BET 枚数が最小のものを選ぶ。
+        // 精算できない (ゲームオーバーとなる) ものは除く。
+        let (bet_count, _, money) = bet_counts(bet_count_max)
+            .filter_map(|bet_count| {
+                let bet = Bet::new(MACHINE, BET_UNIT, bet_count.get()).ok()?;
+                let total = bet.total().ok()?;
+                let payout = prize.calc(bet).ok()?;
+                let money = self.money_limit.settle(self.money, total, payout).ok()?;
+                let income = i64::from(payout.get()) - i64::from(total.get());
+                Some((bet_count, income, money))
             })
-            .min_by_key(|&(bet_count, income)| (-income, bet_count))
+            .min_by_key(|&(bet_count, income, _)| (-income, bet_count))
             .unwrap();
 
-        self.money = self.money.checked_add_signed(income).unwrap();
+        let money_before = std::mem::replace(&mut self.money, money);
 
         let cmv = ConcreteMove::BanditFof {
             rng_index,
@@ -170,7 +181,7 @@ impl State {
             rng_index_before,
             rng_index,
             rng_len: 4,
-            income,
+            money_before,
         };
 
         (cmv, undo)
@@ -181,28 +192,32 @@ impl State {
         bet_count_max: NonZeroUsize,
         rng_index: u8,
     ) -> (ConcreteMove, UndoInfo) {
-        const BET_UNIT: u32 = BET_UNIT_BANDIT_MS;
+        const MACHINE: BanditMachine = BanditMachine::Ms;
+        const BET_UNIT: Money = BET_UNIT_BANDIT_MS;
 
-        debug_assert!(self.money >= BET_UNIT * bet_count_max.get() as u32);
+        debug_assert!(self.money >= BET_UNIT.checked_mul(bet_count_max.get() as u32).unwrap());
 
         let rng_index_before = self.rng.index();
         self.rng.set_index(rng_index);
 
-        let rs = rand_3(&mut self.rng);
+        let rs = self.rng.gen_array();
         let prize = bandit_ms_play(rs);
 
-        // 収入が最大のものを選ぶ。同点なら BET 枚数が最小のものを選ぶ。
-        let (bet_count, income) = bet_counts(bet_count_max)
-            .map(|bet_count| {
-                let gain = prize.calc(BET_UNIT, bet_count) as i32;
-                let loss = (BET_UNIT * bet_count.get() as u32) as i32;
-                let income = gain - loss;
-                (bet_count, income)
+        // 収支が最大のものを選ぶ。同点なら BET 枚数が最小のものを選ぶ。
+        // 精算できない (ゲームオーバーとなる) ものは除く。
+        let (bet_count, _, money) = bet_counts(bet_count_max)
+            .filter_map(|bet_count| {
+                let bet = Bet::new(MACHINE, BET_UNIT, bet_count.get()).ok()?;
+                let total = bet.total().ok()?;
+                let payout = prize.calc(bet).ok()?;
+                let money = self.money_limit.settle(self.money, total, payout).ok()?;
+                let income = i64::from(payout.get()) - i64::from(total.get());
+                Some((bet_count, income, money))
             })
-            .min_by_key(|&(bet_count, income)| (-income, bet_count))
+            .min_by_key(|&(bet_count, income, _)| (-income, bet_count))
             .unwrap();
 
-        self.money = self.money.checked_add_signed(income).unwrap();
+        let money_before = std::mem::replace(&mut self.money, money);
 
         let cmv = ConcreteMove::BanditMs {
             rng_index,
@@ -212,7 +227,7 @@ impl State {
             rng_index_before,
             rng_index,
             rng_len: 3,
-            income,
+            money_before,
         };
 
         (cmv, undo)
@@ -223,28 +238,32 @@ impl State {
         bet_count_max: NonZeroUsize,
         rng_index: u8,
     ) -> (ConcreteMove, UndoInfo) {
-        const BET_UNIT: u32 = BET_UNIT_BANDIT_ROR;
+        const MACHINE: BanditMachine = BanditMachine::Ror;
+        const BET_UNIT: Money = BET_UNIT_BANDIT_ROR;
 
-        debug_assert!(self.money >= BET_UNIT * bet_count_max.get() as u32);
+        debug_assert!(self.money >= BET_UNIT.checked_mul(bet_count_max.get() as u32).unwrap());
 
         let rng_index_before = self.rng.index();
         self.rng.set_index(rng_index);
 
-        let rs = rand_3(&mut self.rng);
+        let rs = self.rng.gen_array();
         let prize = bandit_ror_play(rs);
 
-        // 収入が最大のものを選ぶ。同点なら BET 枚数が最小のものを選ぶ。
-        let (bet_count, income) = bet_counts(bet_count_max)
-            .map(|bet_count| {
-                let gain = prize.calc(BET_UNIT, bet_count) as i32;
-                let loss = (BET_UNIT * bet_count.get() as u32) as i32;
-                let income = gain - loss;
-                (bet_count, income)
+        // 収支が最大のものを選ぶ。同点なら BET 枚数が最小のものを選ぶ。
+        // 精算できない (ゲームオーバーとなる) ものは除く。
+        let (bet_count, _, money) = bet_counts(bet_count_max)
+            .filter_map(|bet_count| {
+                let bet = Bet::new(MACHINE, BET_UNIT, bet_count.get()).ok()?;
+                let total = bet.total().ok()?;
+                let payout = prize.calc(bet).ok()?;
+                let money = self.money_limit.settle(self.money, total, payout).ok()?;
+                let income = i64::from(payout.get()) - i64::from(total.get());
+                Some((bet_count, income, money))
             })
-            .min_by_key(|&(bet_count, income)| (-income, bet_count))
+            .min_by_key(|&(bet_count, income, _)| (-income, bet_count))
             .unwrap();
 
-        self.money = self.money.checked_add_signed(income).unwrap();
+        let money_before = std::mem::replace(&mut self.money, money);
 
         let cmv = ConcreteMove::BanditRor {
             rng_index,
@@ -254,7 +273,7 @@ impl State {
             rng_index_before,
             rng_index,
             rng_len: 3,
-            income,
+            money_before,
         };
 
         (cmv, undo)
@@ -267,23 +286,27 @@ impl State {
         let rng_index_before = self.rng.index();
         self.rng.set_index(rng_index);
 
-        let income = play_poker(&mut self.rng, rng_len);
+        let payout = play_poker(&mut self.rng, rng_len);
 
-        self.money = self.money.checked_add_signed(income).unwrap();
+        let money = self
+            .money_limit
+            .settle(self.money, COST_POKER, payout)
+            .unwrap();
+        let money_before = std::mem::replace(&mut self.money, money);
 
         let cmv = ConcreteMove::Poker { rng_index, rng_len };
         let undo = UndoInfo {
             rng_index_before,
             rng_index,
             rng_len: rng_len.get(),
-            income,
+            money_before,
         };
 
         (cmv, undo)
     }
 
     fn undo_move(&mut self, undo: UndoInfo) {
-        self.money = self.money.checked_add_signed(-undo.income).unwrap();
+        self.money = undo.money_before;
 
         self.rng.undo(undo.rng_index, undo.rng_len);
         self.rng.set_index(undo.rng_index_before);
@@ -362,9 +385,7 @@ impl ConcreteMove {
                     rng_index: index_pre,
                     rng_len: len,
                 },
-            ) => {
-                rng_index_distance(index_pre, index).map_or(false, |d| usize::from(d) == len.get())
-            }
+            ) => rng_index_distance(index_pre, index).is_some_and(|d| usize::from(d) == len.get()),
             _ => false,
         }
     }
@@ -426,20 +447,20 @@ impl std::fmt::Display for ConcreteMovesPretty<'_> {
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 struct UndoInfo {
-    rng_index_before: u8,
+    rng_index_before: RngIndex,
     rng_index: u8,
     rng_len: usize,
-    income: i32,
+    money_before: Money,
 }
 
-/// ポーカーをプレイし、収入を返す。
+/// ポーカーをプレイし、賞金を返す。
 ///
 /// ロイヤルフラッシュしか判定していない(手抜き)。
-fn play_poker(rng: &mut Rng, rng_len: NonZeroUsize) -> i32 {
-    const INCOME_ROYAL: i32 = 50000 - COST_POKER as i32;
-    const INCOME_OTHERS: i32 = -(COST_POKER as i32);
+fn play_poker(rng: &mut Rng, rng_len: NonZeroUsize) -> Money {
+    const PAYOUT_ROYAL: Money = Money::new(50000);
+    const PAYOUT_OTHERS: Money = Money::ZERO;
 
-    const MASK_ROYAL: u32 = (1 << 9) | (1 << 10) | (1 << 11) | (1 << 12) | (1 << 0);
+    const MASK_ROYAL: u16 = (1 << 9) | (1 << 10) | (1 << 11) | (1 << 12) | (1 << 0);
 
     let mut deck = Deck::new();
 
@@ -449,12 +470,10 @@ fn play_poker(rng: &mut Rng, rng_len: NonZeroUsize) -> i32 {
         }};
     }
 
-    // 5 枚引き、各スートの mask を得る。
-    let mut masks_cur = [0; 4];
+    // 5 枚引く。
+    let mut hand = CardSet::new();
     for _ in 0..5 {
-        let card = deal!();
-        let i = usize::from(card.suit().inner());
-        masks_cur[i] |= 1 << card.rank().inner();
+        hand.insert(deal!());
     }
 
     // 注意: 乱数消費量を一定にするため、ここで全てのカードを引き切らなければならない。
@@ -465,13 +484,16 @@ fn play_poker(rng: &mut Rng, rng_len: NonZeroUsize) -> i32 {
     }
 
     // いずれかのスートでロイヤルストレートが成立していたらOK。
-    if masks_cur.into_iter().any(|mask| mask == MASK_ROYAL) {
-        return INCOME_ROYAL;
+    if CardSuit::all()
+        .into_iter()
+        .any(|suit| hand.rank_mask(suit) == MASK_ROYAL)
+    {
+        return PAYOUT_ROYAL;
     }
 
     // カードを交換しないのなら役なし。
     if exchange_count == 0 {
-        return INCOME_OTHERS;
+        return PAYOUT_OTHERS;
     }
 
     // カードを交換する場合、新たに引くカードたちのスートは統一されていなければならない。
@@ -481,44 +503,35 @@ fn play_poker(rng: &mut Rng, rng_len: NonZeroUsize) -> i32 {
 
         let mask_new = 1 << card.rank().inner();
         if (!MASK_ROYAL & mask_new) != 0 {
-            return INCOME_OTHERS;
+            return PAYOUT_OTHERS;
         }
 
         let suit = card.suit();
-        let i = usize::from(suit.inner());
-        let mask_cur = masks_cur[i];
+        let mask_cur = hand.rank_mask(suit);
 
         (suit, mask_cur, mask_new)
     };
 
     for &card in &cards_new[1..] {
         if card.suit() != suit {
-            return INCOME_OTHERS;
+            return PAYOUT_OTHERS;
         }
 
         let bit = 1 << card.rank().inner();
         if (mask_new & bit) != 0 {
-            return INCOME_OTHERS;
+            return PAYOUT_OTHERS;
         }
         if (!MASK_ROYAL & bit) != 0 {
-            return INCOME_OTHERS;
+            return PAYOUT_OTHERS;
         }
 
         mask_new |= bit;
         if ((mask_cur | mask_new) & MASK_ROYAL) == MASK_ROYAL {
-            return INCOME_ROYAL;
+            return PAYOUT_ROYAL;
         }
     }
 
-    INCOME_OTHERS
-}
-
-fn rand_3(rng: &mut Rng) -> [u8; 3] {
-    std::array::from_fn(|_| rng.gen())
-}
-
-fn rand_4(rng: &mut Rng) -> [u8; 4] {
-    std::array::from_fn(|_| rng.gen())
+    PAYOUT_OTHERS
 }
 
 fn bet_counts(bet_count_max: NonZeroUsize) -> impl Iterator<Item = NonZeroUsize> {
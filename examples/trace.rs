@@ -0,0 +1,97 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use caesars_palace_nes::*;
+
+/// エミュレータの記録から乱数の消費を再構成し、`Rng` との食い違いを報告する。
+#[derive(Debug, Parser)]
+struct Cli {
+    /// CPU トレースログ、または RAM ウォッチの CSV (拡張子 `.csv`)。
+    path: PathBuf,
+
+    /// RAM 配置ファイル。省略時はゲーム本体の配置を使う。
+    #[arg(long)]
+    ram_map: Option<PathBuf>,
+
+    /// CPU トレースの開始時点のステートセーブファイル。CPU トレースでは必須。
+    #[arg(long)]
+    savestate: Option<PathBuf>,
+
+    /// RAM ウォッチの 1 フレームあたりの最大消費回数。
+    #[arg(long, default_value_t = 64)]
+    draw_max: usize,
+
+    /// 結果を照合するゲーム (poker, fof, ms, ror)。最後のプレイの乱数列を `--result-savestate` の RAM と比較する。
+    #[arg(long, value_parser = parse_game, requires = "result_savestate")]
+    game: Option<TraceGame>,
+
+    /// プレイ直後のステートセーブファイル。
+    #[arg(long)]
+    result_savestate: Option<PathBuf>,
+
+    /// 再構成した乱数列も出力する。
+    #[arg(long)]
+    verbose: bool,
+}
+
+fn parse_game(s: &str) -> Result<TraceGame, String> {
+    TraceGame::all()
+        .into_iter()
+        .find(|game| game.name() == s)
+        .ok_or_else(|| format!("unknown game: {s}"))
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+
+    let map: RamMap = match &cli.ram_map {
+        Some(path) => std::fs::read_to_string(path)?.parse()?,
+        None => RamMap::game().ok_or("the game's RAM map is not known yet; pass --ram-map")?,
+    };
+    let text = std::fs::read_to_string(&cli.path)?;
+
+    let report = if cli.path.extension().is_some_and(|ext| ext == "csv") {
+        let rows = parse_ram_watch_csv(&text, map.rng)?;
+        TraceReport::replay_ram_watch(&rows, map.rng, cli.draw_max)
+    } else {
+        let savestate = cli
+            .savestate
+            .ok_or("--savestate is required for CPU traces")?;
+        let ram = read_savestate_ram(&std::fs::read(savestate)?)?;
+        let writes = parse_cpu_trace(&text, map.rng)?;
+        TraceReport::replay_cpu_trace(map.rng(&ram), &writes, map.rng)
+    };
+
+    print!("{report}");
+
+    if cli.verbose {
+        for draw in &report.draws {
+            println!(
+                "{}\t0x{:02X}\t0x{:02X}",
+                draw.source, draw.index, draw.value
+            );
+        }
+    }
+
+    let mut consistent = report.is_consistent();
+
+    if let (Some(game), Some(path)) = (cli.game, &cli.result_savestate) {
+        let values = report.values();
+        let rs = values
+            .len()
+            .checked_sub(game.draw_count())
+            .map(|start| &values[start..])
+            .ok_or("too few draws for the game")?;
+        let ram = read_savestate_ram(&std::fs::read(path)?)?;
+        let check = ResultCheck::new(game, rs, &map, &ram)?;
+        print!("{check}");
+        consistent &= check.is_match();
+    }
+
+    if consistent {
+        Ok(())
+    } else {
+        std::process::exit(1);
+    }
+}
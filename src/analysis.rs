@@ -0,0 +1,270 @@
+//! 乱数生成器の長期的な振る舞いの解析。
+
+use crate::rng::{Rng, RngIndex, TABLE_LEN};
+
+/// 乱数生成器を長時間進めたときの状態の推移と、インデックスごとの統計。
+///
+/// 1 回の乱数生成は (インデックス, テーブル) の状態空間上の全単射なので、状態列は純周期的になる。
+/// よって状態の繰り返しは開始状態に戻ったかどうかだけ調べればよく、それもインデックスが
+/// 開始時と同じになったときだけ比較すればよい。
+#[derive(Clone, Debug)]
+pub struct RngEvolution {
+    rng: Rng,
+    /// 周期判定の基準となる状態。範囲外のインデックスから始めた場合は最初の消費後の状態。
+    rng_ref: Option<Rng>,
+    /// 基準となる状態からの消費回数。
+    draws_ref: u64,
+    draws: u64,
+    period: Option<u64>,
+    /// 直前の消費 (インデックス, 出力)。
+    prev: Option<(RngIndex, u8)>,
+    stats: Box<[IndexStats; TABLE_LEN]>,
+}
+
+impl RngEvolution {
+    pub fn new(rng: Rng) -> Self {
+        let rng_ref = (!rng.index().is_out_of_range()).then(|| rng.clone());
+
+        Self {
+            rng,
+            rng_ref,
+            draws_ref: 0,
+            draws: 0,
+            period: None,
+            prev: None,
+            stats: Box::new([IndexStats::default(); TABLE_LEN]),
+        }
+    }
+
+    /// 乱数を `draws` 回生成し、統計を更新する。
+    pub fn run(&mut self, draws: u64) {
+        let mut buf = [0; TABLE_LEN];
+        let mut remain = draws;
+
+        while remain > 0 {
+            let index = self.rng.index();
+
+            // 範囲外のインデックスは 1 回だけ。
+            if index.is_out_of_range() {
+                let r = self.rng.gen();
+                self.record(index, r);
+                self.draws += 1;
+                remain -= 1;
+                self.rng_ref = Some(self.rng.clone());
+                continue;
+            }
+
+            // 基準のインデックスに戻るまでをまとめて生成する。
+            let index_ref = self.rng_ref.as_ref().unwrap().index();
+            let dist =
+                (usize::from(index_ref.get()) + TABLE_LEN - usize::from(index.get())) % TABLE_LEN;
+            let dist = if dist == 0 { TABLE_LEN } else { dist };
+            let n = dist.min(usize::try_from(remain).unwrap_or(usize::MAX));
+
+            let rs = &mut buf[..n];
+            self.rng.fill(rs);
+            let mut index_r = index;
+            for &r in rs.iter() {
+                self.record(index_r, r);
+                index_r = index_r.next();
+            }
+            self.draws += n as u64;
+            self.draws_ref += n as u64;
+            remain -= n as u64;
+
+            if self.period.is_none()
+                && self.rng.index() == index_ref
+                && Some(&self.rng) == self.rng_ref.as_ref()
+            {
+                self.period = Some(self.draws_ref);
+            }
+        }
+    }
+
+    fn record(&mut self, index: RngIndex, r: u8) {
+        let index_slot = index.slot_y();
+        self.stats[index_slot].histogram[usize::from(r)] += 1;
+
+        if let Some((index_prev, r_prev)) = self.prev {
+            let stats = &mut self.stats[index_prev.slot_y()];
+            stats.pair_count += 1;
+            stats.sum_next += u64::from(r);
+            stats.sum_next_sq += u64::from(r) * u64::from(r);
+            stats.sum_product += u64::from(r_prev) * u64::from(r);
+        }
+
+        self.prev = Some((index, r));
+    }
+
+    /// 統計をリセットする (状態と周期判定は維持する)。セッションの局面ごとに集計する際に使う。
+    pub fn reset_stats(&mut self) {
+        self.prev = None;
+        *self.stats = [IndexStats::default(); TABLE_LEN];
+    }
+
+    pub fn rng(&self) -> &Rng {
+        &self.rng
+    }
+
+    /// 総消費回数。
+    pub fn draws(&self) -> u64 {
+        self.draws
+    }
+
+    /// 状態の周期。これまでの消費で開始状態に戻っていなければ `None`。
+    pub fn period(&self) -> Option<u64> {
+        self.period
+    }
+
+    /// インデックス `0..250` ごとの統計 (範囲外のインデックスは同じスロットの通常のインデックスに含める)。
+    pub fn index_stats(&self) -> &[IndexStats; TABLE_LEN] {
+        &self.stats
+    }
+}
+
+/// あるインデックスでの出力の統計。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct IndexStats {
+    histogram: [u64; 256],
+    /// このインデックスの出力と次の出力の組の数。
+    pair_count: u64,
+    sum_next: u64,
+    sum_next_sq: u64,
+    sum_product: u64,
+}
+
+impl Default for IndexStats {
+    fn default() -> Self {
+        Self {
+            histogram: [0; 256],
+            pair_count: 0,
+            sum_next: 0,
+            sum_next_sq: 0,
+            sum_product: 0,
+        }
+    }
+}
+
+impl IndexStats {
+    pub fn histogram(&self) -> &[u64; 256] {
+        &self.histogram
+    }
+
+    pub fn count(&self) -> u64 {
+        self.histogram.iter().sum()
+    }
+
+    /// 出力の平均。出力がなければ `None`。
+    pub fn mean(&self) -> Option<f64> {
+        let count = self.count();
+        let sum: u64 = (0..).zip(&self.histogram).map(|(r, &n)| r * n).sum();
+        (count > 0).then(|| sum as f64 / count as f64)
+    }
+
+    /// 一様分布に対するカイ二乗値 (自由度 255)。出力がなければ `None`。
+    pub fn chi_square(&self) -> Option<f64> {
+        chi_square_uniform(&self.histogram)
+    }
+
+    /// 最も偏った出力値の、一様分布に対する相対的なずれ (0 なら偏りなし)。出力がなければ `None`。
+    pub fn max_bias(&self) -> Option<f64> {
+        let count = self.count();
+        let expected = count as f64 / 256.0;
+        (count > 0).then(|| {
+            self.histogram
+                .iter()
+                .map(|&n| (n as f64 / expected - 1.0).abs())
+                .fold(0.0, f64::max)
+        })
+    }
+
+    /// このインデックスの出力と次の出力の相関係数。
+    ///
+    /// 組がない場合や、どちらかの出力が定数で相関係数が定まらない場合は `None`。
+    pub fn correlation_next(&self) -> Option<f64> {
+        if self.pair_count == 0 {
+            return None;
+        }
+
+        // 組の数と出力の数の差は高々 1 なので、こちらの側の和は度数分布から求める。
+        let n = self.pair_count as f64;
+        let sum: u64 = (0..).zip(&self.histogram).map(|(r, &c)| r * c).sum();
+        let sum_sq: u64 = (0..).zip(&self.histogram).map(|(r, &c)| r * r * c).sum();
+        let count = self.count() as f64;
+
+        let mean = sum as f64 / count;
+        let var = sum_sq as f64 / count - mean * mean;
+        let mean_next = self.sum_next as f64 / n;
+        let var_next = self.sum_next_sq as f64 / n - mean_next * mean_next;
+        let cov = self.sum_product as f64 / n - mean * mean_next;
+
+        let denom = (var * var_next).sqrt();
+        (denom > 0.0).then(|| cov / denom)
+    }
+}
+
+/// 度数分布 `counts` の一様分布に対するカイ二乗値 (自由度 `counts.len() - 1`)。
+/// 度数の合計が 0 なら `None`。
+pub(crate) fn chi_square_uniform(counts: &[u64]) -> Option<f64> {
+    let total: u64 = counts.iter().sum();
+    if total == 0 {
+        return None;
+    }
+
+    let expected = total as f64 / counts.len() as f64;
+    Some(
+        counts
+            .iter()
+            .map(|&n| (n as f64 - expected).powi(2) / expected)
+            .sum(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_period_of_zero_table() {
+        // テーブルが全て 0 なら出力も常に 0 で、周期はインデックスの周期と一致する。
+        let mut evo = RngEvolution::new(Rng::from_raw(17, &[0; TABLE_LEN]));
+        evo.run(1000);
+        assert_eq!(evo.period(), Some(TABLE_LEN as u64));
+        assert_eq!(evo.index_stats()[0].histogram()[0], 4);
+        // 出力が定数なので相関係数は定まらない。
+        assert_eq!(evo.index_stats()[0].correlation_next(), None);
+
+        let mut evo = RngEvolution::new(Rng::from_raw(252, &[0; TABLE_LEN]));
+        evo.run(1000);
+        assert_eq!(evo.period(), Some(TABLE_LEN as u64));
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut evo = RngEvolution::new(Rng::new());
+        evo.run(250 * 400 + 7);
+        assert_eq!(evo.draws(), 250 * 400 + 7);
+        assert_eq!(evo.period(), None);
+
+        let mut rng = Rng::new();
+        rng.fill(&mut vec![0; 250 * 400 + 7]);
+        assert_eq!(evo.rng(), &rng);
+
+        let stats = &evo.index_stats()[3];
+        assert_eq!(stats.count(), 401);
+        let mean = stats.mean().unwrap();
+        assert!(mean > 96.0 && mean < 160.0);
+        assert!(stats.correlation_next().unwrap().abs() < 0.3);
+
+        let total: u64 = evo.index_stats().iter().map(IndexStats::count).sum();
+        assert_eq!(total, evo.draws());
+
+        evo.reset_stats();
+        let stats = &evo.index_stats()[3];
+        assert_eq!(stats.count(), 0);
+        assert_eq!(stats.mean(), None);
+        assert_eq!(stats.chi_square(), None);
+        assert_eq!(stats.max_bias(), None);
+        assert_eq!(stats.correlation_next(), None);
+    }
+}
@@ -0,0 +1,256 @@
+use std::num::NonZeroUsize;
+
+use crate::money::{Money, MoneyError};
+
+/// スロットマシンの種類。
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum BanditMachine {
+    /// "Fountains of Fortune"
+    Fof,
+    /// "Magnificent Sevens"
+    Ms,
+    /// "Riches of Rome"
+    Ror,
+}
+
+impl BanditMachine {
+    pub const fn all() -> [Self; 3] {
+        [Self::Fof, Self::Ms, Self::Ror]
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Fof => "Fountains of Fortune",
+            Self::Ms => "Magnificent Sevens",
+            Self::Ror => "Riches of Rome",
+        }
+    }
+
+    /// 賭けられる最大枚数。
+    ///
+    /// `examples/tas.rs` が前提としていた値。ROM/RAM からは確認していない。
+    pub const fn bet_count_max(self) -> usize {
+        3
+    }
+
+    /// 選べる BET 単位 (1 枚あたりの金額)。
+    ///
+    /// `examples/tas.rs` の定数 `BET_UNIT_BANDIT_*` と、"Fountains of Fortune" では $5 も選べるという
+    /// そのコメントから取った値で、ROM/RAM からは確認していない。
+    /// 実際にはここにない BET 単位も選べるかもしれない。
+    pub const fn bet_units(self) -> &'static [Money] {
+        const FOF: [Money; 2] = [Money::new(1), Money::new(5)];
+        const MS: [Money; 1] = [Money::new(100)];
+        const ROR: [Money; 1] = [Money::new(500)];
+
+        match self {
+            Self::Fof => &FOF,
+            Self::Ms => &MS,
+            Self::Ror => &ROR,
+        }
+    }
+}
+
+impl std::fmt::Display for BanditMachine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// スロットマシンへの BET。生成時に台ごとの制約を検証済み。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Bet {
+    machine: BanditMachine,
+    unit: Money,
+    count: NonZeroUsize,
+}
+
+impl Bet {
+    /// 台 `machine` に 1 枚あたり `unit` で `count` 枚賭ける。
+    ///
+    /// `unit` は `machine.bet_units()` のいずれかでなければならない。
+    pub fn new(machine: BanditMachine, unit: Money, count: usize) -> Result<Self, BetError> {
+        if !machine.bet_units().contains(&unit) {
+            return Err(BetError::UnitNotAllowed { machine, unit });
+        }
+        let count = NonZeroUsize::new(count)
+            .filter(|count| count.get() <= machine.bet_count_max())
+            .ok_or(BetError::CountOutOfRange { machine, count })?;
+
+        Ok(Self {
+            machine,
+            unit,
+            count,
+        })
+    }
+
+    pub fn machine(self) -> BanditMachine {
+        self.machine
+    }
+
+    pub fn unit(self) -> Money {
+        self.unit
+    }
+
+    pub fn count(self) -> NonZeroUsize {
+        self.count
+    }
+
+    /// 賭け金の合計。
+    pub fn total(self) -> Result<Money, MoneyError> {
+        self.unit.checked_mul(self.count.get() as u32)
+    }
+
+    /// この BET が台 `machine` のものか検証する。
+    pub(crate) fn expect_machine(self, machine: BanditMachine) -> Result<(), BetError> {
+        if self.machine == machine {
+            Ok(())
+        } else {
+            Err(BetError::MachineMismatch {
+                expected: machine,
+                actual: self.machine,
+            })
+        }
+    }
+}
+
+/// BET や賞金計算のエラー。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BetError {
+    /// その台では選べない BET 単位。
+    UnitNotAllowed { machine: BanditMachine, unit: Money },
+    /// BET 枚数が `1..=bet_count_max()` の範囲外。
+    CountOutOfRange {
+        machine: BanditMachine,
+        count: usize,
+    },
+    /// 別の台の BET で賞金を計算しようとした。
+    MachineMismatch {
+        expected: BanditMachine,
+        actual: BanditMachine,
+    },
+    /// 役と BET 枚数の組み合わせが存在しない。
+    Impossible {
+        machine: BanditMachine,
+        rank: usize,
+        count: NonZeroUsize,
+    },
+    /// 金額の計算エラー。
+    Money(MoneyError),
+}
+
+impl From<MoneyError> for BetError {
+    fn from(e: MoneyError) -> Self {
+        Self::Money(e)
+    }
+}
+
+impl std::fmt::Display for BetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnitNotAllowed { machine, unit } => {
+                write!(f, "{machine}: bet unit {unit} is not available")
+            }
+            Self::CountOutOfRange { machine, count } => write!(
+                f,
+                "{machine}: bet count {count} is out of range 1..={}",
+                machine.bet_count_max()
+            ),
+            Self::MachineMismatch { expected, actual } => {
+                write!(f, "bet for {actual} was used for {expected}")
+            }
+            Self::Impossible {
+                machine,
+                rank,
+                count,
+            } => write!(
+                f,
+                "{machine}: rank {rank} cannot be paid with {count} coins"
+            ),
+            Self::Money(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for BetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Money(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bandit::{bandit_ms_play, BanditFofPrize};
+
+    use super::*;
+
+    #[test]
+    fn test_bet_validation() {
+        let unit = Money::new(100);
+
+        let bet = Bet::new(BanditMachine::Ms, unit, 3).unwrap();
+        assert_eq!(bet.total(), Ok(Money::new(300)));
+
+        for count in [0, 4] {
+            assert_eq!(
+                Bet::new(BanditMachine::Ms, unit, count),
+                Err(BetError::CountOutOfRange {
+                    machine: BanditMachine::Ms,
+                    count
+                })
+            );
+        }
+        for machine in BanditMachine::all() {
+            for &unit in machine.bet_units() {
+                assert!(Bet::new(machine, unit, 1).is_ok());
+            }
+            for unit in [0, 2, 10, 1000].map(Money::new) {
+                assert_eq!(
+                    Bet::new(machine, unit, 1),
+                    Err(BetError::UnitNotAllowed { machine, unit })
+                );
+            }
+        }
+        assert_eq!(
+            Bet::new(BanditMachine::Ms, Money::new(500), 1),
+            Err(BetError::UnitNotAllowed {
+                machine: BanditMachine::Ms,
+                unit: Money::new(500)
+            })
+        );
+    }
+
+    #[test]
+    fn test_calc_errors() {
+        let unit = Money::new(5);
+        let bet_fof = Bet::new(BanditMachine::Fof, unit, 3).unwrap();
+
+        // ジャックポットは 3 枚賭けなら倍率表によらない。
+        assert_eq!(
+            BanditFofPrize::AllGreenSeven.calc(bet_fof),
+            Ok(Money::new(750))
+        );
+        assert_eq!(
+            BanditFofPrize::Normal { rank: 6 }.calc(bet_fof),
+            Err(BetError::Impossible {
+                machine: BanditMachine::Fof,
+                rank: 6,
+                count: bet_fof.count()
+            })
+        );
+        assert!(matches!(
+            BanditFofPrize::Normal { rank: 8 }.calc(bet_fof),
+            Err(BetError::Impossible { rank: 8, .. })
+        ));
+        assert_eq!(
+            bandit_ms_play([0; 3]).calc(bet_fof),
+            Err(BetError::MachineMismatch {
+                expected: BanditMachine::Ms,
+                actual: BanditMachine::Fof
+            })
+        );
+    }
+}
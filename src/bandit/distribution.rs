@@ -0,0 +1,366 @@
+//! スロットマシンの賞金の厳密な確率分布。
+//!
+//! 各リールの位置は 1 つの乱数だけから決まるので、乱数をそのリールに出るシンボルで類別し、
+//! 類の代表と個数の直積を列挙すれば、全ての乱数列を列挙するのと同じ分布が得られる。
+//!
+//! 関数はいずれも本クレートのテーブルを使う。他のバージョンでは `GameVersion` の同名のメソッドを使う。
+
+use crate::game_version::{GameVersion, PayTables};
+use crate::money::Money;
+use crate::rng::{Rng, RngIndex};
+use std::collections::BTreeMap;
+
+use super::*;
+
+/// スロットマシンのプレイ結果。
+pub trait BanditPrize: Copy + Ord {
+    /// このプレイ結果を返す台。
+    const MACHINE: BanditMachine;
+
+    /// `pay_tables` の倍率で、`bet` で賭けたときの賞金を返す。
+    fn payout(self, pay_tables: &PayTables, bet: Bet) -> Result<Money, BetError>;
+}
+
+impl BanditPrize for BanditFofPrize {
+    const MACHINE: BanditMachine = BanditMachine::Fof;
+
+    fn payout(self, pay_tables: &PayTables, bet: Bet) -> Result<Money, BetError> {
+        self.calc_with(&pay_tables.fof, &pay_tables.fof_jackpot_seeds, bet)
+    }
+}
+
+// "Magnificent Sevens", "Riches of Rome" の結果はプレイ時の倍率を含むので、`pay_tables` は使わない。
+
+impl BanditPrize for BanditMsPrize {
+    const MACHINE: BanditMachine = BanditMachine::Ms;
+
+    fn payout(self, _pay_tables: &PayTables, bet: Bet) -> Result<Money, BetError> {
+        self.calc(bet)
+    }
+}
+
+impl BanditPrize for BanditRorPrize {
+    const MACHINE: BanditMachine = BanditMachine::Ror;
+
+    fn payout(self, _pay_tables: &PayTables, bet: Bet) -> Result<Money, BetError> {
+        self.calc(bet)
+    }
+}
+
+/// 賞金ごとの入力 (乱数列またはインデックス) の個数。
+///
+/// 賞金は分布を求めたバージョンの倍率で計算する。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PrizeDistribution<P> {
+    counts: BTreeMap<P, u64>,
+    pay_tables: PayTables,
+}
+
+impl<P: BanditPrize> PrizeDistribution<P> {
+    pub fn counts(&self) -> &BTreeMap<P, u64> {
+        &self.counts
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    pub fn probability(&self, prize: P) -> f64 {
+        let count = self.counts.get(&prize).copied().unwrap_or(0);
+        count as f64 / self.total() as f64
+    }
+
+    /// 賞金の期待値。
+    pub fn expected_payout(&self, bet: Bet) -> Result<f64, BetError> {
+        let mut sum = 0.0;
+        for (&prize, &count) in &self.counts {
+            sum += f64::from(prize.payout(&self.pay_tables, bet)?.get()) * count as f64;
+        }
+        Ok(sum / self.total() as f64)
+    }
+
+    /// 賞金が 0 でない確率。
+    pub fn hit_frequency(&self, bet: Bet) -> Result<f64, BetError> {
+        let mut count_hit = 0;
+        for (&prize, &count) in &self.counts {
+            if prize.payout(&self.pay_tables, bet)? > Money::ZERO {
+                count_hit += count;
+            }
+        }
+        Ok(count_hit as f64 / self.total() as f64)
+    }
+
+    /// 賞金の分散。
+    pub fn variance(&self, bet: Bet) -> Result<f64, BetError> {
+        let mean = self.expected_payout(bet)?;
+        let mut sum = 0.0;
+        for (&prize, &count) in &self.counts {
+            let payout = f64::from(prize.payout(&self.pay_tables, bet)?.get());
+            sum += (payout - mean).powi(2) * count as f64;
+        }
+        Ok(sum / self.total() as f64)
+    }
+
+    /// 還元率 (賞金の期待値 / BET 額)。
+    pub fn rtp(&self, bet: Bet) -> Result<f64, BetError> {
+        Ok(self.expected_payout(bet)? / f64::from(bet.total()?.get()))
+    }
+
+    /// 全ての乱数列について、リールごとの乱数の類の直積を列挙して分布を求める。
+    fn enumerate<const N: usize>(
+        pay_tables: &PayTables,
+        classes: &[Vec<(u8, u64)>; N],
+        play: impl Fn([u8; N]) -> P,
+    ) -> Self {
+        let mut counts = BTreeMap::new();
+
+        let mut choice = [0; N];
+        'outer: loop {
+            let rs = std::array::from_fn(|j| classes[j][choice[j]].0);
+            let count: u64 = (0..N).map(|j| classes[j][choice[j]].1).product();
+            *counts.entry(play(rs)).or_insert(0) += count;
+
+            for j in 0..N {
+                choice[j] += 1;
+                if choice[j] < classes[j].len() {
+                    continue 'outer;
+                }
+                choice[j] = 0;
+            }
+            break;
+        }
+
+        Self {
+            counts,
+            pay_tables: pay_tables.clone(),
+        }
+    }
+
+    /// `rng` の全インデックス (`0..=255`) からプレイしたときの分布を求める。
+    fn from_rng<const N: usize>(
+        pay_tables: &PayTables,
+        rng: &Rng,
+        play: impl Fn([u8; N]) -> P,
+    ) -> Self {
+        let mut counts = BTreeMap::new();
+
+        let mut rng = rng.clone();
+        for index in RngIndex::all() {
+            rng.set_index(index);
+            let mut rs = rng.peek_iter();
+            let prize = play(std::array::from_fn(|_| rs.next().unwrap()));
+            *counts.entry(prize).or_insert(0) += 1;
+        }
+
+        Self {
+            counts,
+            pay_tables: pay_tables.clone(),
+        }
+    }
+}
+
+/// 乱数列の `j` 番目の乱数を、`key` (その乱数だけで決まるシンボルなど) で類別する。
+/// 各類の (代表, 個数) を返す。
+fn classes<const N: usize, K: PartialEq>(j: usize, key: impl Fn([u8; N]) -> K) -> Vec<(u8, u64)> {
+    let mut classes: Vec<(K, u8, u64)> = vec![];
+
+    for r in 0..=0xFF {
+        let mut rs = [0; N];
+        rs[j] = r;
+        let k = key(rs);
+
+        match classes.iter_mut().find(|(k_class, _, _)| *k_class == k) {
+            Some((_, _, count)) => *count += 1,
+            None => classes.push((k, r, 1)),
+        }
+    }
+
+    classes
+        .into_iter()
+        .map(|(_, r, count)| (r, count))
+        .collect()
+}
+
+/// "Fountains of Fortune" の、一様な乱数列に対する賞金の分布。
+pub fn bandit_fof_distribution() -> PrizeDistribution<BanditFofPrize> {
+    fof_distribution_with(&GameVersion::default())
+}
+
+/// "Magnificent Sevens" の、一様な乱数列に対する賞金の分布。
+pub fn bandit_ms_distribution() -> PrizeDistribution<BanditMsPrize> {
+    ms_distribution_with(&GameVersion::default())
+}
+
+/// "Riches of Rome" の、一様な乱数列に対する賞金の分布。
+pub fn bandit_ror_distribution() -> PrizeDistribution<BanditRorPrize> {
+    ror_distribution_with(&GameVersion::default())
+}
+
+pub(crate) fn fof_distribution_with(version: &GameVersion) -> PrizeDistribution<BanditFofPrize> {
+    PrizeDistribution::enumerate(&version.pay_tables, &fof_classes(version), |rs| {
+        version.bandit_fof_play(rs)
+    })
+}
+
+pub(crate) fn ms_distribution_with(version: &GameVersion) -> PrizeDistribution<BanditMsPrize> {
+    PrizeDistribution::enumerate(&version.pay_tables, &ms_classes(version), |rs| {
+        version.bandit_ms_play(rs)
+    })
+}
+
+pub(crate) fn ror_distribution_with(version: &GameVersion) -> PrizeDistribution<BanditRorPrize> {
+    PrizeDistribution::enumerate(&version.pay_tables, &ror_classes(version), |rs| {
+        version.bandit_ror_play(rs)
+    })
+}
+
+// 乱数は逆順に適用されるので、j 番目の乱数は末尾から j 番目のリールに対応する。
+
+fn fof_classes(version: &GameVersion) -> [Vec<(u8, u64)>; 4] {
+    std::array::from_fn(|j| classes(j, |rs| version.bandit_fof_symbols(rs)[3 - j]))
+}
+
+fn ms_classes(version: &GameVersion) -> [Vec<(u8, u64)>; 3] {
+    std::array::from_fn(|j| classes(j, |rs| version.bandit_ms_lines(rs).map(|line| line[2 - j])))
+}
+
+fn ror_classes(version: &GameVersion) -> [Vec<(u8, u64)>; 3] {
+    std::array::from_fn(|j| classes(j, |rs| version.bandit_ror_lines(rs).map(|line| line[2 - j])))
+}
+
+/// "Fountains of Fortune" を `rng` の全インデックスからプレイしたときの賞金の分布。
+pub fn bandit_fof_distribution_rng(rng: &Rng) -> PrizeDistribution<BanditFofPrize> {
+    fof_distribution_rng_with(&GameVersion::default(), rng)
+}
+
+/// "Magnificent Sevens" を `rng` の全インデックスからプレイしたときの賞金の分布。
+pub fn bandit_ms_distribution_rng(rng: &Rng) -> PrizeDistribution<BanditMsPrize> {
+    ms_distribution_rng_with(&GameVersion::default(), rng)
+}
+
+/// "Riches of Rome" を `rng` の全インデックスからプレイしたときの賞金の分布。
+pub fn bandit_ror_distribution_rng(rng: &Rng) -> PrizeDistribution<BanditRorPrize> {
+    ror_distribution_rng_with(&GameVersion::default(), rng)
+}
+
+pub(crate) fn fof_distribution_rng_with(
+    version: &GameVersion,
+    rng: &Rng,
+) -> PrizeDistribution<BanditFofPrize> {
+    PrizeDistribution::from_rng(&version.pay_tables, rng, |rs| version.bandit_fof_play(rs))
+}
+
+pub(crate) fn ms_distribution_rng_with(
+    version: &GameVersion,
+    rng: &Rng,
+) -> PrizeDistribution<BanditMsPrize> {
+    PrizeDistribution::from_rng(&version.pay_tables, rng, |rs| version.bandit_ms_play(rs))
+}
+
+pub(crate) fn ror_distribution_rng_with(
+    version: &GameVersion,
+    rng: &Rng,
+) -> PrizeDistribution<BanditRorPrize> {
+    PrizeDistribution::from_rng(&version.pay_tables, rng, |rs| version.bandit_ror_play(rs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uniform_distribution() {
+        let fof = bandit_fof_distribution();
+        assert_eq!(fof.total(), 1 << 32);
+        let ms = bandit_ms_distribution();
+        assert_eq!(ms.total(), 1 << 24);
+        let ror = bandit_ror_distribution();
+        assert_eq!(ror.total(), 1 << 24);
+
+        // "Riches of Rome" の中央行の左端がチェリーなら倍率 5 または 2 となる。
+        let cherry_count = (0..=0xFF)
+            .filter(|&r| bandit_ror_lines([0, 0, r])[0][0] == BanditRorSymbol::Cherry)
+            .count() as u64;
+        let count: u64 = ror
+            .counts()
+            .iter()
+            .filter(|(prize, _)| matches!(prize.factors()[0], 5 | 2))
+            .map(|(_, &count)| count)
+            .sum();
+        assert_eq!(count, cherry_count << 16);
+
+        // 先頭の乱数を固定した総当たりと一致する。
+        for r0 in [0, 77, 200] {
+            let version = GameVersion::default();
+            let mut classes = ror_classes(&version);
+            classes[0] = vec![(r0, 1)];
+            let dist = PrizeDistribution::enumerate(&version.pay_tables, &classes, bandit_ror_play);
+
+            let mut counts = BTreeMap::new();
+            for r1 in 0..=0xFF {
+                for r2 in 0..=0xFF {
+                    *counts.entry(bandit_ror_play([r0, r1, r2])).or_insert(0) += 1;
+                }
+            }
+            assert_eq!(dist.counts(), &counts);
+
+            let mut classes = ms_classes(&version);
+            classes[0] = vec![(r0, 1)];
+            let dist = PrizeDistribution::enumerate(&version.pay_tables, &classes, bandit_ms_play);
+
+            let mut counts = BTreeMap::new();
+            for r1 in 0..=0xFF {
+                for r2 in 0..=0xFF {
+                    *counts.entry(bandit_ms_play([r0, r1, r2])).or_insert(0) += 1;
+                }
+            }
+            assert_eq!(dist.counts(), &counts);
+        }
+
+        let rtp = ms
+            .rtp(Bet::new(BanditMachine::Ms, Money::new(100), 1).unwrap())
+            .unwrap();
+        assert!(rtp > 0.0 && rtp.is_finite());
+
+        // 1 枚賭けでは中央行の左端がチェリーなら必ず当たる。
+        let unit = Money::new(500);
+        let bet_1 = Bet::new(BanditMachine::Ror, unit, 1).unwrap();
+        let bet_3 = Bet::new(BanditMachine::Ror, unit, 3).unwrap();
+        let hit_frequency = ror.hit_frequency(bet_1).unwrap();
+        assert!(hit_frequency >= cherry_count as f64 / 256.0);
+        assert!(hit_frequency < ror.hit_frequency(bet_3).unwrap());
+        assert!(ror.variance(bet_1).unwrap() > 0.0);
+        assert!(matches!(
+            Bet::new(BanditMachine::Ror, Money::MAX, 3),
+            Err(BetError::UnitNotAllowed { .. })
+        ));
+        assert!(matches!(
+            ms.rtp(bet_1),
+            Err(BetError::MachineMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_rng_distribution() {
+        let rng = Rng::new();
+        let dist = bandit_fof_distribution_rng(&rng);
+        assert_eq!(dist.total(), 256);
+
+        let mut rng_play = rng.clone();
+        let null_count = (0..=0xFF)
+            .filter(|&index| {
+                rng_play.set_index(index);
+                let rs = rng_play.gen_array();
+                rng_play.undo(index, 4);
+                bandit_fof_play(rs) == BanditFofPrize::Null
+            })
+            .count() as u64;
+        assert_eq!(
+            dist.counts()
+                .get(&BanditFofPrize::Null)
+                .copied()
+                .unwrap_or(0),
+            null_count
+        );
+    }
+}
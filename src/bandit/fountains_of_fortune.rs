@@ -1,7 +1,10 @@
-use std::num::NonZeroUsize;
+use crate::money::Money;
+
+use super::bet::{BanditMachine, Bet, BetError};
+use super::pay_table::{PayFactor, PayTable, PayTableEntry};
 
 /// スロットマシン "Fountains of Fortune" のプレイ結果。
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum BanditFofPrize {
     Null,
     Normal { rank: usize },
@@ -10,57 +13,153 @@ pub enum BanditFofPrize {
 }
 
 impl BanditFofPrize {
-    pub fn calc(self, bet_unit: u32, bet_count: NonZeroUsize) -> u32 {
-        const FACTOR_TABLE: [[u32; 3]; 9] = [
-            [2, 4, 6],
-            [5, 10, 15],
-            [10, 20, 30],
-            [10, 20, 30],
-            [20, 40, 60],
-            [100, 200, 300],
-            [200, 400, 0],
-            [2000, 5000, 0],
-            [100, 200, 300],
-        ];
+    pub fn calc(self, bet: Bet) -> Result<Money, BetError> {
+        self.calc_with(&FACTOR_TABLE, &JACKPOT_SEEDS, bet)
+    }
 
+    /// 賞金倍率テーブルの行。はずれなら `None`。
+    pub(crate) fn rank(self) -> Option<usize> {
         match self {
-            Self::Null => 0,
-            Self::Normal { rank } => {
-                let factor = FACTOR_TABLE[rank][bet_count.get() - 1];
-                bet_unit * factor
-            }
-            Self::AllGreenSeven => {
-                const FACTORS: [u32; 2] = [200, 400];
-                if bet_count.get() == 3 {
-                    // NOTE: 小ジャックポットの最小金額。実際には時間とともに少しずつ増える。
-                    750
-                } else {
-                    bet_unit * FACTORS[bet_count.get() - 1]
-                }
-            }
-            Self::AllBlackSeven => {
-                const FACTORS: [u32; 2] = [2000, 5000];
-                if bet_count.get() == 3 {
-                    // NOTE: 大ジャックポットの最小金額。実際には時間とともに少しずつ増える。
-                    100000
-                } else {
-                    bet_unit * FACTORS[bet_count.get() - 1]
-                }
-            }
+            Self::Null => None,
+            Self::Normal { rank } => Some(rank),
+            Self::AllGreenSeven => Some(RANK_ALL_GREEN_SEVEN),
+            Self::AllBlackSeven => Some(RANK_ALL_BLACK_SEVEN),
         }
     }
+
+    /// 3 枚賭けでジャックポットとなるか。
+    pub fn is_jackpot(self) -> bool {
+        matches!(self, Self::AllGreenSeven | Self::AllBlackSeven)
+    }
+
+    /// 指定した賞金倍率テーブルとジャックポットの最小金額を用いて賞金を計算する。
+    pub(crate) fn calc_with(
+        self,
+        factor_table: &[[u32; 3]; 8],
+        jackpot_seeds: &[u32; 2],
+        bet: Bet,
+    ) -> Result<Money, BetError> {
+        bet.expect_machine(BanditMachine::Fof)?;
+
+        let count = bet.count();
+        let rank = match self {
+            Self::Null => return Ok(Money::ZERO),
+            Self::AllGreenSeven if count.get() == 3 => return Ok(Money::new(jackpot_seeds[0])),
+            Self::AllBlackSeven if count.get() == 3 => return Ok(Money::new(jackpot_seeds[1])),
+            _ => self.rank().unwrap(),
+        };
+
+        // 倍率 0 は組み合わせが存在しないことを表す。
+        let factor = factor_table
+            .get(rank)
+            .map(|row| row[count.get() - 1])
+            .filter(|&factor| factor != 0)
+            .ok_or(BetError::Impossible {
+                machine: BanditMachine::Fof,
+                rank,
+                count,
+            })?;
+
+        Ok(bet.unit().checked_mul(factor)?)
+    }
 }
 
+/// 役ごと、BET 枚数ごとの賞金倍率。
+///
+/// ジャックポットの行の 3 枚目は使われない (代わりにジャックポットの金額となる)。
+/// 0 はその組み合わせが存在しないことを表す。
+/// 各行はいずれかの役から参照される。
+pub(crate) const FACTOR_TABLE: [[u32; 3]; 8] = [
+    [2, 4, 6],
+    [5, 10, 15],
+    [10, 20, 30],
+    [10, 20, 30],
+    [20, 40, 60],
+    [100, 200, 300],
+    [200, 400, 0],
+    [2000, 5000, 0],
+];
+
+/// 3 枚賭けのジャックポットの最小金額 (緑 7, 黒 7 の順)。
+///
+/// NOTE: 実際には時間とともに少しずつ増える。
+pub(crate) const JACKPOT_SEEDS: [u32; 2] = [750, 100000];
+
+const RANK_ALL_GREEN_SEVEN: usize = 6;
+const RANK_ALL_BLACK_SEVEN: usize = 7;
+
 /// スロットマシン "Fountains of Fortune" を指定した乱数列でプレイし、結果を返す。
 pub fn bandit_fof_play(rs: [u8; 4]) -> BanditFofPrize {
-    let reels = randoms_to_reels(rs);
-    let reels = reels.map(|reel| reel + 2);
-    let syms = reels_to_symbols(reels);
+    play_with(&REEL_TABLES, &SYMBOL_TABLES, rs)
+}
+
+/// スロットマシン "Fountains of Fortune" を指定した乱数列でプレイし、成立した役を含む結果を返す。
+pub fn bandit_fof_play_detail(rs: [u8; 4]) -> BanditFofDetail {
+    BanditFofDetail::new(bandit_fof_symbols(rs))
+}
+
+/// スロットマシン "Fountains of Fortune" の配当表。
+pub fn bandit_fof_pay_table() -> PayTable {
+    pay_table_with(&FACTOR_TABLE)
+}
+
+/// スロットマシン "Fountains of Fortune" を指定した乱数列でプレイし、ライン上のシンボルを返す。
+pub fn bandit_fof_symbols(rs: [u8; 4]) -> [BanditFofSymbol; 4] {
+    symbols_with(&REEL_TABLES, &SYMBOL_TABLES, rs)
+}
+
+pub(crate) fn play_with(
+    reel_tables: &[[u8; 64]; 2],
+    symbol_tables: &[[BanditFofSymbol; 11]; 2],
+    rs: [u8; 4],
+) -> BanditFofPrize {
+    let syms = symbols_with(reel_tables, symbol_tables, rs);
     calc_prize(syms)
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-enum Symbol {
+/// 役ごとに、BET 枚数 1, 2, 3 枚の賞金倍率を並べる。
+pub(crate) fn pay_table_with(factor_table: &[[u32; 3]; 8]) -> PayTable {
+    let entries = BanditFofRule::all()
+        .into_iter()
+        .map(|rule| {
+            let prize = rule.prize();
+            let row = factor_table[prize.rank().unwrap()];
+            let factors = (0..3)
+                .map(|i| {
+                    if prize.is_jackpot() && i == 2 {
+                        PayFactor::Jackpot
+                    } else {
+                        PayFactor::Times(row[i])
+                    }
+                })
+                .collect();
+            PayTableEntry {
+                pattern: rule.label(),
+                reels: vec![],
+                factors,
+            }
+        })
+        .collect();
+
+    PayTable {
+        machine: BanditMachine::Fof,
+        entries,
+    }
+}
+
+pub(crate) fn symbols_with(
+    reel_tables: &[[u8; 64]; 2],
+    symbol_tables: &[[BanditFofSymbol; 11]; 2],
+    rs: [u8; 4],
+) -> [BanditFofSymbol; 4] {
+    let reels = randoms_to_reels(reel_tables, rs);
+    let reels = reels.map(|reel| reel + 2);
+    reels_to_symbols(symbol_tables, reels)
+}
+
+/// スロットマシン "Fountains of Fortune" のシンボル。
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum BanditFofSymbol {
     Null,
     Cherry,
     RedSeven,
@@ -68,78 +167,246 @@ enum Symbol {
     BlackSeven,
 }
 
-impl Symbol {
+impl BanditFofSymbol {
     pub fn is_seven(self) -> bool {
-        use Symbol::*;
+        use BanditFofSymbol::*;
 
         matches!(self, RedSeven | GreenSeven | BlackSeven)
     }
 }
 
-fn randoms_to_reels(rs: [u8; 4]) -> [u8; 4] {
-    #[rustfmt::skip]
-    const TABLES: [[u8; 64]; 2] = [
-        [
-            0x00, 0x01, 0x01, 0x01, 0x01, 0x02, 0x02, 0x03, 0x03, 0x03, 0x03, 0x04, 0x04, 0x05, 0x05, 0x05,
-            0x05, 0x06, 0x06, 0x07, 0x07, 0x07, 0x07, 0x08, 0x08, 0x09, 0x09, 0x09, 0x09, 0x0A, 0x0A, 0x0B,
-            0x0B, 0x0B, 0x0B, 0x0C, 0x0C, 0x0D, 0x0D, 0x0D, 0x0D, 0x0E, 0x0E, 0x0F, 0x0F, 0x0F, 0x0F, 0x10,
-            0x10, 0x11, 0x11, 0x11, 0x11, 0x12, 0x12, 0x13, 0x13, 0x13, 0x13, 0x14, 0x14, 0x15, 0x15, 0x15,
-        ],
+/// スロットマシン "Fountains of Fortune" の役 (賞金の対象となるシンボルの組み合わせ)。
+///
+/// 上から順に判定され、最初に成立したものが採用される。
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum BanditFofRule {
+    FourBlackSevens,
+    ThreeBlackSevens,
+    FourGreenSevens,
+    ThreeGreenSevens,
+    FourRedSevens,
+    ThreeRedSevens,
+    FourAnySevens,
+    ThreeAnySevens,
+    FourCherries,
+    ThreeCherries,
+    TwoCherries,
+    OneCherry,
+}
+
+impl BanditFofRule {
+    /// 判定順に全ての役を返す。
+    pub const fn all() -> [Self; 12] {
+        use BanditFofRule::*;
+
         [
-            0x00, 0x01, 0x01, 0x01, 0x01, 0x02, 0x02, 0x02, 0x03, 0x03, 0x03, 0x04, 0x04, 0x05, 0x05, 0x05,
-            0x05, 0x06, 0x06, 0x07, 0x07, 0x07, 0x07, 0x08, 0x08, 0x09, 0x09, 0x09, 0x09, 0x0A, 0x0A, 0x0B,
-            0x0B, 0x0B, 0x0B, 0x0C, 0x0C, 0x0D, 0x0D, 0x0D, 0x0D, 0x0E, 0x0E, 0x0F, 0x0F, 0x0F, 0x0F, 0x10,
-            0x10, 0x11, 0x11, 0x11, 0x11, 0x12, 0x12, 0x13, 0x13, 0x13, 0x13, 0x14, 0x14, 0x15, 0x15, 0x15,
-        ],
-    ];
+            FourBlackSevens,
+            ThreeBlackSevens,
+            FourGreenSevens,
+            ThreeGreenSevens,
+            FourRedSevens,
+            ThreeRedSevens,
+            FourAnySevens,
+            ThreeAnySevens,
+            FourCherries,
+            ThreeCherries,
+            TwoCherries,
+            OneCherry,
+        ]
+    }
+
+    pub const fn label(self) -> &'static str {
+        use BanditFofRule::*;
+
+        match self {
+            FourBlackSevens => "four black sevens",
+            ThreeBlackSevens => "three black sevens",
+            FourGreenSevens => "four green sevens",
+            ThreeGreenSevens => "three green sevens",
+            FourRedSevens => "four red sevens",
+            ThreeRedSevens => "three red sevens",
+            FourAnySevens => "four sevens",
+            ThreeAnySevens => "three sevens",
+            FourCherries => "four cherries",
+            ThreeCherries => "three cherries",
+            TwoCherries => "two cherries",
+            OneCherry => "one cherry",
+        }
+    }
+
+    /// この役が成立したときのプレイ結果。
+    pub const fn prize(self) -> BanditFofPrize {
+        use BanditFofRule::*;
+
+        match self {
+            FourBlackSevens => BanditFofPrize::AllBlackSeven,
+            FourGreenSevens => BanditFofPrize::AllGreenSeven,
+            ThreeBlackSevens | FourRedSevens => BanditFofPrize::Normal { rank: 5 },
+            ThreeGreenSevens | FourCherries => BanditFofPrize::Normal { rank: 4 },
+            ThreeRedSevens | FourAnySevens => BanditFofPrize::Normal { rank: 3 },
+            ThreeCherries => BanditFofPrize::Normal { rank: 2 },
+            ThreeAnySevens | TwoCherries => BanditFofPrize::Normal { rank: 1 },
+            OneCherry => BanditFofPrize::Normal { rank: 0 },
+        }
+    }
 
+    /// この役の対象となるシンボルか。
+    pub fn matches(self, sym: BanditFofSymbol) -> bool {
+        use BanditFofRule::*;
+        use BanditFofSymbol::*;
+
+        match self {
+            FourBlackSevens | ThreeBlackSevens => sym == BlackSeven,
+            FourGreenSevens | ThreeGreenSevens => sym == GreenSeven,
+            FourRedSevens | ThreeRedSevens => sym == RedSeven,
+            FourAnySevens | ThreeAnySevens => sym.is_seven(),
+            FourCherries | ThreeCherries | TwoCherries | OneCherry => sym == Cherry,
+        }
+    }
+}
+
+impl std::fmt::Display for BanditFofRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// スロットマシン "Fountains of Fortune" のプレイ結果の詳細。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct BanditFofDetail {
+    symbols: [BanditFofSymbol; 4],
+    rule: Option<BanditFofRule>,
+    factor_table: [[u32; 3]; 8],
+    jackpot_seeds: [u32; 2],
+}
+
+impl BanditFofDetail {
+    pub(crate) fn new(symbols: [BanditFofSymbol; 4]) -> Self {
+        Self::with_table(&FACTOR_TABLE, &JACKPOT_SEEDS, symbols)
+    }
+
+    /// 賞金の計算に指定した賞金倍率テーブルとジャックポットの最小金額を用いる。
+    pub(crate) fn with_table(
+        factor_table: &[[u32; 3]; 8],
+        jackpot_seeds: &[u32; 2],
+        symbols: [BanditFofSymbol; 4],
+    ) -> Self {
+        Self {
+            symbols,
+            rule: calc_rule(symbols),
+            factor_table: *factor_table,
+            jackpot_seeds: *jackpot_seeds,
+        }
+    }
+
+    /// ライン上のシンボル。
+    pub fn symbols(self) -> [BanditFofSymbol; 4] {
+        self.symbols
+    }
+
+    /// 成立した役。はずれなら `None`。
+    pub fn rule(self) -> Option<BanditFofRule> {
+        self.rule
+    }
+
+    pub fn prize(self) -> BanditFofPrize {
+        self.rule.map_or(BanditFofPrize::Null, BanditFofRule::prize)
+    }
+
+    /// 役の対象となったリール。
+    pub fn matched_reels(self) -> [bool; 4] {
+        self.symbols
+            .map(|sym| self.rule.is_some_and(|rule| rule.matches(sym)))
+    }
+
+    /// BET 枚数 1, 2, 3 枚それぞれの賞金 (ゲーム内の配当表の 1 行に相当)。
+    pub fn payout_row(self, bet_unit: Money) -> Result<[Money; 3], BetError> {
+        let mut row = [Money::ZERO; 3];
+        for (i, payout) in row.iter_mut().enumerate() {
+            let bet = Bet::new(BanditMachine::Fof, bet_unit, i + 1)?;
+            *payout = self
+                .prize()
+                .calc_with(&self.factor_table, &self.jackpot_seeds, bet)?;
+        }
+        Ok(row)
+    }
+}
+
+/// 乱数からリール位置への変換テーブル。
+#[rustfmt::skip]
+pub(crate) const REEL_TABLES: [[u8; 64]; 2] = [
+    [
+        0x00, 0x01, 0x01, 0x01, 0x01, 0x02, 0x02, 0x03, 0x03, 0x03, 0x03, 0x04, 0x04, 0x05, 0x05, 0x05,
+        0x05, 0x06, 0x06, 0x07, 0x07, 0x07, 0x07, 0x08, 0x08, 0x09, 0x09, 0x09, 0x09, 0x0A, 0x0A, 0x0B,
+        0x0B, 0x0B, 0x0B, 0x0C, 0x0C, 0x0D, 0x0D, 0x0D, 0x0D, 0x0E, 0x0E, 0x0F, 0x0F, 0x0F, 0x0F, 0x10,
+        0x10, 0x11, 0x11, 0x11, 0x11, 0x12, 0x12, 0x13, 0x13, 0x13, 0x13, 0x14, 0x14, 0x15, 0x15, 0x15,
+    ],
+    [
+        0x00, 0x01, 0x01, 0x01, 0x01, 0x02, 0x02, 0x02, 0x03, 0x03, 0x03, 0x04, 0x04, 0x05, 0x05, 0x05,
+        0x05, 0x06, 0x06, 0x07, 0x07, 0x07, 0x07, 0x08, 0x08, 0x09, 0x09, 0x09, 0x09, 0x0A, 0x0A, 0x0B,
+        0x0B, 0x0B, 0x0B, 0x0C, 0x0C, 0x0D, 0x0D, 0x0D, 0x0D, 0x0E, 0x0E, 0x0F, 0x0F, 0x0F, 0x0F, 0x10,
+        0x10, 0x11, 0x11, 0x11, 0x11, 0x12, 0x12, 0x13, 0x13, 0x13, 0x13, 0x14, 0x14, 0x15, 0x15, 0x15,
+    ],
+];
+
+/// 乱数列から各リールの停止位置を求める。
+pub(crate) fn randoms_to_reels(reel_tables: &[[u8; 64]; 2], rs: [u8; 4]) -> [u8; 4] {
     const BASE_IDXS: [usize; 4] = [18, 10, 2, 16];
 
     // 乱数は逆順に適用される。
     std::array::from_fn(|i| {
         let j = [0, 1, 1, 0][i];
         let idx = (BASE_IDXS[i] + usize::from(rs[3 - i])) % 64;
-        TABLES[j][idx]
+        reel_tables[j][idx]
     })
 }
 
-fn reels_to_symbols(reels: [u8; 4]) -> [Symbol; 4] {
-    const TABLES: [[Symbol; 11]; 2] = {
-        use Symbol::*;
+/// リール位置からシンボルへの変換テーブル。
+pub(crate) const SYMBOL_TABLES: [[BanditFofSymbol; 11]; 2] = {
+    use BanditFofSymbol::*;
+    [
         [
-            [
-                BlackSeven, GreenSeven, GreenSeven, GreenSeven, RedSeven, GreenSeven, GreenSeven,
-                GreenSeven, Cherry, GreenSeven, GreenSeven,
-            ],
-            [
-                BlackSeven, RedSeven, RedSeven, RedSeven, GreenSeven, RedSeven, RedSeven, RedSeven,
-                Cherry, RedSeven, RedSeven,
-            ],
-        ]
-    };
+            BlackSeven, GreenSeven, GreenSeven, GreenSeven, RedSeven, GreenSeven, GreenSeven,
+            GreenSeven, Cherry, GreenSeven, GreenSeven,
+        ],
+        [
+            BlackSeven, RedSeven, RedSeven, RedSeven, GreenSeven, RedSeven, RedSeven, RedSeven,
+            Cherry, RedSeven, RedSeven,
+        ],
+    ]
+};
 
+fn reels_to_symbols(
+    symbol_tables: &[[BanditFofSymbol; 11]; 2],
+    reels: [u8; 4],
+) -> [BanditFofSymbol; 4] {
     std::array::from_fn(|i| {
         let reel = reels[i];
         if reel % 2 == 0 {
             let j = [0, 1, 1, 0][i];
             let idx = usize::from(reel % 22 / 2);
-            TABLES[j][idx]
+            symbol_tables[j][idx]
         } else {
-            Symbol::Null
+            BanditFofSymbol::Null
         }
     })
 }
 
-fn calc_prize(syms: [Symbol; 4]) -> BanditFofPrize {
-    use Symbol::*;
+fn calc_prize(syms: [BanditFofSymbol; 4]) -> BanditFofPrize {
+    calc_rule(syms).map_or(BanditFofPrize::Null, BanditFofRule::prize)
+}
+
+fn calc_rule(syms: [BanditFofSymbol; 4]) -> Option<BanditFofRule> {
+    use BanditFofRule::*;
+    use BanditFofSymbol::*;
 
     // 黒 7 による役。
     {
         let count = syms.into_iter().filter(|&sym| sym == BlackSeven).count();
         if count == 4 {
-            return BanditFofPrize::AllBlackSeven;
+            return Some(FourBlackSevens);
         } else if count == 3 {
-            return BanditFofPrize::Normal { rank: 5 };
+            return Some(ThreeBlackSevens);
         }
     }
 
@@ -147,9 +414,9 @@ fn calc_prize(syms: [Symbol; 4]) -> BanditFofPrize {
     {
         let count = syms.into_iter().filter(|&sym| sym == GreenSeven).count();
         if count == 4 {
-            return BanditFofPrize::AllGreenSeven;
+            return Some(FourGreenSevens);
         } else if count == 3 {
-            return BanditFofPrize::Normal { rank: 4 };
+            return Some(ThreeGreenSevens);
         }
     }
 
@@ -157,9 +424,9 @@ fn calc_prize(syms: [Symbol; 4]) -> BanditFofPrize {
     {
         let count = syms.into_iter().filter(|&sym| sym == RedSeven).count();
         if count == 4 {
-            return BanditFofPrize::Normal { rank: 5 };
+            return Some(FourRedSevens);
         } else if count == 3 {
-            return BanditFofPrize::Normal { rank: 3 };
+            return Some(ThreeRedSevens);
         }
     }
 
@@ -167,9 +434,9 @@ fn calc_prize(syms: [Symbol; 4]) -> BanditFofPrize {
     {
         let count = syms.into_iter().filter(|&sym| sym.is_seven()).count();
         if count == 4 {
-            return BanditFofPrize::Normal { rank: 3 };
+            return Some(FourAnySevens);
         } else if count == 3 {
-            return BanditFofPrize::Normal { rank: 1 };
+            return Some(ThreeAnySevens);
         }
     }
 
@@ -177,13 +444,104 @@ fn calc_prize(syms: [Symbol; 4]) -> BanditFofPrize {
     {
         let count = syms.into_iter().filter(|&sym| sym == Cherry).count();
         match count {
-            4 => return BanditFofPrize::Normal { rank: 4 },
-            3 => return BanditFofPrize::Normal { rank: 2 },
-            2 => return BanditFofPrize::Normal { rank: 1 },
-            1 => return BanditFofPrize::Normal { rank: 0 },
+            4 => return Some(FourCherries),
+            3 => return Some(ThreeCherries),
+            2 => return Some(TwoCherries),
+            1 => return Some(OneCherry),
             _ => {}
         }
     }
 
-    BanditFofPrize::Null
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    fn test_detail() {
+        use BanditFofSymbol::*;
+
+        let detail = BanditFofDetail::new([GreenSeven, Cherry, GreenSeven, GreenSeven]);
+        assert_eq!(detail.rule(), Some(BanditFofRule::ThreeGreenSevens));
+        assert_eq!(detail.rule().unwrap().label(), "three green sevens");
+        assert_eq!(detail.matched_reels(), [true, false, true, true]);
+        assert_eq!(
+            detail.payout_row(Money::new(5)),
+            Ok([100, 200, 300].map(Money::new))
+        );
+
+        let detail = BanditFofDetail::new([BlackSeven; 4]);
+        assert_eq!(detail.prize(), BanditFofPrize::AllBlackSeven);
+        assert_eq!(
+            detail.payout_row(Money::new(5)),
+            Ok([10000, 25000, 100000].map(Money::new))
+        );
+
+        let detail = BanditFofDetail::new([Null, RedSeven, Null, GreenSeven]);
+        assert_eq!(detail.rule(), None);
+        assert_eq!(detail.matched_reels(), [false; 4]);
+
+        // 全てのシンボルの組について、役を分割する前の判定 (色ごとの個数による) と一致する。
+        let expected = |syms: [BanditFofSymbol; 4]| {
+            let count = |f: fn(BanditFofSymbol) -> bool| syms.into_iter().filter(|&s| f(s)).count();
+            let black = count(|s| s == BlackSeven);
+            let green = count(|s| s == GreenSeven);
+            let red = count(|s| s == RedSeven);
+            let seven = count(BanditFofSymbol::is_seven);
+            let cherry = count(|s| s == Cherry);
+            match (black, green, red, seven, cherry) {
+                (4, ..) => BanditFofPrize::AllBlackSeven,
+                (_, 4, ..) => BanditFofPrize::AllGreenSeven,
+                (3, ..) | (_, _, 4, ..) => BanditFofPrize::Normal { rank: 5 },
+                (_, 3, ..) => BanditFofPrize::Normal { rank: 4 },
+                (_, _, 3, ..) | (_, _, _, 4, _) => BanditFofPrize::Normal { rank: 3 },
+                (_, _, _, 3, _) => BanditFofPrize::Normal { rank: 1 },
+                (.., 4) => BanditFofPrize::Normal { rank: 4 },
+                (.., 3) => BanditFofPrize::Normal { rank: 2 },
+                (.., 2) => BanditFofPrize::Normal { rank: 1 },
+                (.., 1) => BanditFofPrize::Normal { rank: 0 },
+                _ => BanditFofPrize::Null,
+            }
+        };
+        const SYMBOLS: [BanditFofSymbol; 5] = [Null, Cherry, RedSeven, GreenSeven, BlackSeven];
+        let mut counts = BTreeMap::new();
+        for i in 0..SYMBOLS.len().pow(4) {
+            let syms =
+                std::array::from_fn(|j| SYMBOLS[i / SYMBOLS.len().pow(j as u32) % SYMBOLS.len()]);
+            let prize = BanditFofDetail::new(syms).prize();
+            assert_eq!(prize, expected(syms), "{syms:?}");
+            *counts.entry(prize).or_insert(0) += 1;
+        }
+        let rank = |rank| BanditFofPrize::Normal { rank };
+        assert_eq!(
+            counts,
+            BTreeMap::from([
+                (BanditFofPrize::Null, 67),
+                (rank(0), 148),
+                (rank(1), 288),
+                (rank(2), 16),
+                (rank(3), 70),
+                (rank(4), 17),
+                (rank(5), 17),
+                (BanditFofPrize::AllGreenSeven, 1),
+                (BanditFofPrize::AllBlackSeven, 1),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_factor_table_rows_are_used() {
+        for rank in 0..FACTOR_TABLE.len() {
+            assert!(
+                BanditFofRule::all()
+                    .into_iter()
+                    .any(|rule| rule.prize().rank() == Some(rank)),
+                "row {rank} is not used by any rule"
+            );
+        }
+    }
 }
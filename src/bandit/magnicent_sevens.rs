@@ -1,7 +1,10 @@
-use std::num::NonZeroUsize;
+use crate::money::Money;
+
+use super::bet::{BanditMachine, Bet, BetError};
+use super::pay_table::{PayFactor, PayTable, PayTableEntry};
 
 /// スロットマシン "Magnificent Sevens" のプレイ結果。
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct BanditMsPrize {
     factors: [u32; 3],
 }
@@ -16,31 +19,93 @@ impl BanditMsPrize {
         self.factors
     }
 
-    pub fn calc(self, bet_unit: u32, bet_count: NonZeroUsize) -> u32 {
-        self.factors
+    pub fn calc(self, bet: Bet) -> Result<Money, BetError> {
+        bet.expect_machine(BanditMachine::Ms)?;
+
+        let payout = self
+            .factors
             .into_iter()
-            .take(bet_count.get())
-            .map(|factor| bet_unit * factor)
-            .sum()
+            .take(bet.count().get())
+            .try_fold(Money::ZERO, |sum, factor| {
+                sum.checked_add(bet.unit().checked_mul(factor)?)
+            })?;
+        Ok(payout)
     }
 }
 
 /// スロットマシン "Magnificent Sevens" を指定した乱数列でプレイし、結果を返す。
 pub fn bandit_ms_play(rs: [u8; 3]) -> BanditMsPrize {
-    let reels = randoms_to_reels(rs);
+    play_with(&REEL_TABLES, &SYMBOL_TABLE, &FACTORS, rs)
+}
+
+/// スロットマシン "Magnificent Sevens" の配当表。倍率は 1 ライン (BET 1 枚) あたり。
+pub fn bandit_ms_pay_table() -> PayTable {
+    pay_table_with(&FACTORS)
+}
+
+/// スロットマシン "Magnificent Sevens" を指定した乱数列でプレイし、各行のシンボルを返す。
+///
+/// 行は (中央行, 上行, 下行) の順 (BET 順と同じ)。
+pub fn bandit_ms_lines(rs: [u8; 3]) -> [[BanditMsSymbol; 3]; 3] {
+    lines_with(&REEL_TABLES, &SYMBOL_TABLE, rs)
+}
+
+pub(crate) fn play_with(
+    reel_tables: &[[u8; 66]; 3],
+    symbol_table: &[BanditMsSymbol; 22],
+    factors: &[u32; 5],
+    rs: [u8; 3],
+) -> BanditMsPrize {
+    let lines = lines_with(reel_tables, symbol_table, rs);
+    let factors = lines.map(|syms| calc_rule(syms).map_or(0, |rule| factors[rule.rank()]));
+
+    BanditMsPrize::new(factors)
+}
+
+pub(crate) fn lines_with(
+    reel_tables: &[[u8; 66]; 3],
+    symbol_table: &[BanditMsSymbol; 22],
+    rs: [u8; 3],
+) -> [[BanditMsSymbol; 3]; 3] {
+    let reels = randoms_to_reels(reel_tables, rs);
 
-    let factors = std::array::from_fn(|i| {
+    std::array::from_fn(|i| {
         const BIASS: [u8; 3] = [2, 0, 4];
         let reels = reels.map(|reel| reel.wrapping_add(BIASS[i]));
-        let syms = reels_to_symbols(reels);
-        prize_factor(syms)
-    });
+        reels_to_symbols(symbol_table, reels)
+    })
+}
 
-    BanditMsPrize::new(factors)
+pub(crate) fn pay_table_with(factors: &[u32; 5]) -> PayTable {
+    let entries = BanditMsRule::all()
+        .into_iter()
+        .map(|rule| PayTableEntry {
+            pattern: rule.label(),
+            reels: rule.reel_symbols().map(reel_label).to_vec(),
+            factors: vec![PayFactor::Times(factors[rule.rank()])],
+        })
+        .collect();
+
+    PayTable {
+        machine: BanditMachine::Ms,
+        entries,
+    }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-enum Symbol {
+/// 配当表の 1 リール分の表記。
+fn reel_label(syms: &[BanditMsSymbol]) -> String {
+    syms.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// 役ごとの賞金倍率。`BanditMsRule::all()` の順。
+pub(crate) const FACTORS: [u32; 5] = [1000, 100, 50, 20, 10];
+
+/// スロットマシン "Magnificent Sevens" のシンボル。
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum BanditMsSymbol {
     Blank,
     BlackSeven,
     RedSeven,
@@ -49,80 +114,187 @@ enum Symbol {
     TripleBar,
 }
 
-impl Symbol {
-    fn is_seven(self) -> bool {
-        use Symbol::*;
+impl std::fmt::Display for BanditMsSymbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use BanditMsSymbol::*;
+
+        let s = match self {
+            Blank => "BLANK",
+            BlackSeven => "BLACK 7",
+            RedSeven => "RED 7",
+            SingleBar => "BAR",
+            DoubleBar => "2 BAR",
+            TripleBar => "3 BAR",
+        };
+        f.write_str(s)
+    }
+}
+
+/// スロットマシン "Magnificent Sevens" の役 (1 ライン上のシンボルの組み合わせ)。
+///
+/// 上から順に判定され、最初に成立したものが採用される。判定順は賞金の高い順でもある。
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum BanditMsRule {
+    ThreeBlackSevens,
+    ThreeSevens,
+    ThreeTripleBars,
+    ThreeDoubleBars,
+    ThreeBars,
+}
+
+impl BanditMsRule {
+    /// 判定順に全ての役を返す。
+    pub const fn all() -> [Self; 5] {
+        use BanditMsRule::*;
 
-        matches!(self, BlackSeven | RedSeven)
+        [
+            ThreeBlackSevens,
+            ThreeSevens,
+            ThreeTripleBars,
+            ThreeDoubleBars,
+            ThreeBars,
+        ]
     }
 
-    fn is_bar(self) -> bool {
-        use Symbol::*;
+    pub const fn label(self) -> &'static str {
+        use BanditMsRule::*;
 
-        matches!(self, SingleBar | DoubleBar | TripleBar)
+        match self {
+            ThreeBlackSevens => "three black sevens",
+            ThreeSevens => "any three sevens",
+            ThreeTripleBars => "three triple bars",
+            ThreeDoubleBars => "three double bars",
+            ThreeBars => "any three bars",
+        }
+    }
+
+    /// 各リール (左から) で対象となるシンボル。
+    pub const fn reel_symbols(self) -> [&'static [BanditMsSymbol]; 3] {
+        use BanditMsRule::*;
+        use BanditMsSymbol::*;
+
+        match self {
+            ThreeBlackSevens => [&[BlackSeven]; 3],
+            ThreeSevens => [&[BlackSeven, RedSeven]; 3],
+            ThreeTripleBars => [&[TripleBar]; 3],
+            ThreeDoubleBars => [&[DoubleBar]; 3],
+            ThreeBars => [&[SingleBar, DoubleBar, TripleBar]; 3],
+        }
+    }
+
+    /// ライン上のシンボルがこの役を満たすか。
+    pub fn matches(self, syms: [BanditMsSymbol; 3]) -> bool {
+        syms.iter()
+            .zip(self.reel_symbols())
+            .all(|(sym, targets)| targets.contains(sym))
+    }
+
+    /// 賞金倍率テーブルの行 (判定順の番号)。
+    pub(crate) fn rank(self) -> usize {
+        self as usize
     }
 }
 
-fn randoms_to_reels(rs: [u8; 3]) -> [u8; 3] {
-    #[rustfmt::skip]
-    const TABLE: [[u8; 66]; 3] = [
-        [
-            0x00, 0x02, 0x04, 0x06, 0x08, 0x0A, 0x0C, 0x0E, 0x10, 0x12, 0x14, 0x16, 0x18, 0x1A, 0x1C, 0x1E,
-            0x20, 0x22, 0x24, 0x26, 0x28, 0x2A, 0x00, 0x02, 0x04, 0x06, 0x08, 0x0A, 0x1C, 0x1E, 0x18, 0x1A,
-            0x0C, 0x0E, 0x08, 0x0A, 0x0C, 0x0E, 0x08, 0x0A, 0x0C, 0x0E, 0x08, 0x0A, 0x0C, 0x0E, 0x08, 0x0A,
-            0x0C, 0x0E, 0x10, 0x12, 0x14, 0x16, 0x04, 0x06, 0x10, 0x12, 0x14, 0x16, 0x20, 0x22, 0x24, 0x26,
-            0x28, 0x2A,
-        ],
-        [
-            0x00, 0x02, 0x04, 0x06, 0x08, 0x0A, 0x0C, 0x0E, 0x10, 0x12, 0x14, 0x16, 0x18, 0x1A, 0x1C, 0x1E,
-            0x20, 0x22, 0x24, 0x26, 0x28, 0x2A, 0x00, 0x02, 0x04, 0x06, 0x08, 0x0A, 0x1C, 0x1E, 0x18, 0x1A,
-            0x1C, 0x1E, 0x18, 0x1A, 0x1C, 0x1E, 0x18, 0x0A, 0x0C, 0x0E, 0x08, 0x0A, 0x0C, 0x0E, 0x08, 0x0A,
-            0x0C, 0x0E, 0x10, 0x12, 0x14, 0x16, 0x04, 0x06, 0x10, 0x12, 0x24, 0x26, 0x24, 0x26, 0x24, 0x26,
-            0x28, 0x2A,
-        ],
-        [
-            0x00, 0x02, 0x04, 0x06, 0x08, 0x0A, 0x0C, 0x0E, 0x10, 0x12, 0x14, 0x16, 0x18, 0x1A, 0x1C, 0x1E,
-            0x20, 0x22, 0x24, 0x26, 0x28, 0x2A, 0x00, 0x02, 0x04, 0x06, 0x08, 0x0A, 0x1C, 0x1E, 0x18, 0x1A,
-            0x1C, 0x1E, 0x18, 0x1A, 0x0C, 0x0E, 0x08, 0x0A, 0x0C, 0x0E, 0x08, 0x0A, 0x0C, 0x0E, 0x08, 0x0A,
-            0x0C, 0x0E, 0x10, 0x12, 0x14, 0x16, 0x04, 0x06, 0x10, 0x12, 0x24, 0x26, 0x14, 0x16, 0x20, 0x22,
-            0x28, 0x2A,
-        ],
-    ];
+impl std::fmt::Display for BanditMsRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// 乱数からリール位置への変換テーブル。
+#[rustfmt::skip]
+pub(crate) const REEL_TABLES: [[u8; 66]; 3] = [
+    [
+        0x00, 0x02, 0x04, 0x06, 0x08, 0x0A, 0x0C, 0x0E, 0x10, 0x12, 0x14, 0x16, 0x18, 0x1A, 0x1C, 0x1E,
+        0x20, 0x22, 0x24, 0x26, 0x28, 0x2A, 0x00, 0x02, 0x04, 0x06, 0x08, 0x0A, 0x1C, 0x1E, 0x18, 0x1A,
+        0x0C, 0x0E, 0x08, 0x0A, 0x0C, 0x0E, 0x08, 0x0A, 0x0C, 0x0E, 0x08, 0x0A, 0x0C, 0x0E, 0x08, 0x0A,
+        0x0C, 0x0E, 0x10, 0x12, 0x14, 0x16, 0x04, 0x06, 0x10, 0x12, 0x14, 0x16, 0x20, 0x22, 0x24, 0x26,
+        0x28, 0x2A,
+    ],
+    [
+        0x00, 0x02, 0x04, 0x06, 0x08, 0x0A, 0x0C, 0x0E, 0x10, 0x12, 0x14, 0x16, 0x18, 0x1A, 0x1C, 0x1E,
+        0x20, 0x22, 0x24, 0x26, 0x28, 0x2A, 0x00, 0x02, 0x04, 0x06, 0x08, 0x0A, 0x1C, 0x1E, 0x18, 0x1A,
+        0x1C, 0x1E, 0x18, 0x1A, 0x1C, 0x1E, 0x18, 0x0A, 0x0C, 0x0E, 0x08, 0x0A, 0x0C, 0x0E, 0x08, 0x0A,
+        0x0C, 0x0E, 0x10, 0x12, 0x14, 0x16, 0x04, 0x06, 0x10, 0x12, 0x24, 0x26, 0x24, 0x26, 0x24, 0x26,
+        0x28, 0x2A,
+    ],
+    [
+        0x00, 0x02, 0x04, 0x06, 0x08, 0x0A, 0x0C, 0x0E, 0x10, 0x12, 0x14, 0x16, 0x18, 0x1A, 0x1C, 0x1E,
+        0x20, 0x22, 0x24, 0x26, 0x28, 0x2A, 0x00, 0x02, 0x04, 0x06, 0x08, 0x0A, 0x1C, 0x1E, 0x18, 0x1A,
+        0x1C, 0x1E, 0x18, 0x1A, 0x0C, 0x0E, 0x08, 0x0A, 0x0C, 0x0E, 0x08, 0x0A, 0x0C, 0x0E, 0x08, 0x0A,
+        0x0C, 0x0E, 0x10, 0x12, 0x14, 0x16, 0x04, 0x06, 0x10, 0x12, 0x24, 0x26, 0x14, 0x16, 0x20, 0x22,
+        0x28, 0x2A,
+    ],
+];
 
+/// 乱数列から各リールの停止位置を求める。
+pub(crate) fn randoms_to_reels(reel_tables: &[[u8; 66]; 3], rs: [u8; 3]) -> [u8; 3] {
     const BASE_IDXS: [usize; 3] = [40, 10, 24];
 
     // 乱数は逆順に適用される。
     std::array::from_fn(|i| {
         let idx = (BASE_IDXS[i] + usize::from(rs[2 - i])) % 66;
-        TABLE[i][idx]
+        reel_tables[i][idx]
     })
 }
 
-fn reels_to_symbols(reels: [u8; 3]) -> [Symbol; 3] {
-    const TABLE: [Symbol; 22] = {
-        use Symbol::*;
-        [
-            BlackSeven, Blank, DoubleBar, Blank, TripleBar, Blank, RedSeven, Blank, TripleBar,
-            Blank, SingleBar, Blank, DoubleBar, Blank, RedSeven, Blank, DoubleBar, Blank,
-            SingleBar, Blank, SingleBar, Blank,
-        ]
-    };
+/// リール位置からシンボルへの変換テーブル。
+pub(crate) const SYMBOL_TABLE: [BanditMsSymbol; 22] = {
+    use BanditMsSymbol::*;
+    [
+        BlackSeven, Blank, DoubleBar, Blank, TripleBar, Blank, RedSeven, Blank, TripleBar, Blank,
+        SingleBar, Blank, DoubleBar, Blank, RedSeven, Blank, DoubleBar, Blank, SingleBar, Blank,
+        SingleBar, Blank,
+    ]
+};
 
+fn reels_to_symbols(symbol_table: &[BanditMsSymbol; 22], reels: [u8; 3]) -> [BanditMsSymbol; 3] {
     std::array::from_fn(|i| {
         let idx = usize::from(reels[i] % 44 / 2);
-        TABLE[idx]
+        symbol_table[idx]
     })
 }
 
-fn prize_factor(syms: [Symbol; 3]) -> u32 {
-    use Symbol::*;
+/// 成立した役を返す。
+fn calc_rule(syms: [BanditMsSymbol; 3]) -> Option<BanditMsRule> {
+    BanditMsRule::all()
+        .into_iter()
+        .find(|rule| rule.matches(syms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules() {
+        use BanditMsSymbol::*;
+
+        let factor = |syms| calc_rule(syms).map_or(0, |rule| FACTORS[rule.rank()]);
+        assert_eq!(factor([BlackSeven, BlackSeven, BlackSeven]), 1000);
+        assert_eq!(factor([RedSeven, RedSeven, RedSeven]), 100);
+        assert_eq!(factor([BlackSeven, RedSeven, BlackSeven]), 100);
+        assert_eq!(factor([TripleBar, TripleBar, TripleBar]), 50);
+        assert_eq!(factor([DoubleBar, DoubleBar, DoubleBar]), 20);
+        assert_eq!(factor([SingleBar, SingleBar, SingleBar]), 10);
+        assert_eq!(factor([TripleBar, TripleBar, DoubleBar]), 10);
+        assert_eq!(factor([SingleBar, DoubleBar, TripleBar]), 10);
+
+        assert_eq!(factor([BlackSeven, BlackSeven, Blank]), 0);
+        assert_eq!(factor([RedSeven, RedSeven, SingleBar]), 0);
+        assert_eq!(factor([Blank, Blank, Blank]), 0);
 
-    match syms {
-        [BlackSeven, BlackSeven, BlackSeven] => 1000,
-        _ if syms.into_iter().all(Symbol::is_seven) => 100,
-        [TripleBar, TripleBar, TripleBar] => 50,
-        [DoubleBar, DoubleBar, DoubleBar] => 20,
-        _ if syms.into_iter().all(Symbol::is_bar) => 10,
-        _ => 0,
+        let table = bandit_ms_pay_table();
+        let factors: Vec<_> = table
+            .entries
+            .iter()
+            .map(|entry| entry.factors.clone())
+            .collect();
+        assert_eq!(
+            factors,
+            [1000, 100, 50, 20, 10].map(|f| vec![PayFactor::Times(f)])
+        );
+        assert_eq!(table.entries[1].pattern, "any three sevens");
+        assert_eq!(table.entries[1].reels, ["BLACK 7/RED 7"; 3]);
     }
 }
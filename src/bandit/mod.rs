@@ -1,7 +1,13 @@
-mod fountains_of_fortune;
-mod magnicent_sevens;
-mod riches_of_rome;
+mod bet;
+pub(crate) mod distribution;
+pub(crate) mod fountains_of_fortune;
+pub(crate) mod magnicent_sevens;
+mod pay_table;
+pub(crate) mod riches_of_rome;
 
+pub use self::bet::*;
+pub use self::distribution::*;
 pub use self::fountains_of_fortune::*;
 pub use self::magnicent_sevens::*;
+pub use self::pay_table::*;
 pub use self::riches_of_rome::*;
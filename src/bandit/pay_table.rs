@@ -0,0 +1,167 @@
+use super::bet::BanditMachine;
+
+/// 配当表の 1 マス。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PayFactor {
+    /// BET 単位に対する賞金倍率。
+    Times(u32),
+    /// ジャックポット (金額は倍率によらない)。
+    Jackpot,
+}
+
+impl std::fmt::Display for PayFactor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Times(factor) => factor.fmt(f),
+            Self::Jackpot => f.write_str("JACKPOT"),
+        }
+    }
+}
+
+/// 配当表の 1 行。
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PayTableEntry {
+    /// 役を表すシンボルの組み合わせ。
+    pub pattern: &'static str,
+    /// リールごと (左から) の対象シンボルの表記。位置によらない役 ("Fountains of Fortune") では空。
+    pub reels: Vec<String>,
+    /// 賞金倍率。"Fountains of Fortune" は BET 枚数 1, 2, 3 枚ごと、他は 1 ラインあたりの 1 つのみ。
+    pub factors: Vec<PayFactor>,
+}
+
+/// スロットマシンの配当表。賞金計算と同じ定数から生成される。
+///
+/// `Display` は役を判定順に 1 行ずつ、賞金倍率を列に並べた単純なテキスト表現で、
+/// ゲーム内の配当表画面の再現ではない (画面での行の順序や表記は確認できていない)。
+/// 数値をゲーム画面と 1 つずつ見比べるためのもの。
+/// 役がリールの位置で決まる台では、役の名前の代わりにリールごとの列にシンボルを並べる。
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PayTable {
+    pub machine: BanditMachine,
+    pub entries: Vec<PayTableEntry>,
+}
+
+impl std::fmt::Display for PayTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const FACTOR_WIDTH: usize = 8;
+        const REEL_GAP: usize = 2;
+
+        let column_count = self
+            .entries
+            .iter()
+            .map(|entry| entry.factors.len())
+            .max()
+            .unwrap_or(0);
+
+        // 役の列: リールごとの列があればそれを、なければ役の名前を使う。
+        let reel_count = self
+            .entries
+            .iter()
+            .map(|entry| entry.reels.len())
+            .max()
+            .unwrap_or(0);
+        let pattern_widths: Vec<usize> = if reel_count > 0 {
+            (0..reel_count)
+                .map(|i| {
+                    let width = self
+                        .entries
+                        .iter()
+                        .filter_map(|entry| entry.reels.get(i))
+                        .map(String::len)
+                        .max()
+                        .unwrap_or(0);
+                    width.max(format!("REEL {}", i + 1).len()) + REEL_GAP
+                })
+                .collect()
+        } else {
+            let width = self
+                .entries
+                .iter()
+                .map(|entry| entry.pattern.len())
+                .max()
+                .unwrap_or(0);
+            vec![width]
+        };
+
+        writeln!(f, "{}", self.machine)?;
+
+        if reel_count > 0 {
+            for (i, width) in pattern_widths.iter().enumerate() {
+                write!(f, "{:width$}", format!("REEL {}", i + 1))?;
+            }
+        } else {
+            write!(f, "{:width$}", "", width = pattern_widths[0])?;
+        }
+
+        // 倍率の列が複数ある場合は BET 枚数ごと。
+        if column_count > 1 {
+            for count in 1..=column_count {
+                write!(f, "{:>FACTOR_WIDTH$}", format!("{count} COIN"))?;
+            }
+        } else {
+            write!(f, "{:>FACTOR_WIDTH$}", "PAYS")?;
+        }
+        writeln!(f)?;
+
+        for entry in &self.entries {
+            if reel_count > 0 {
+                for (i, width) in pattern_widths.iter().enumerate() {
+                    let reel = entry.reels.get(i).map_or("", String::as_str);
+                    write!(f, "{reel:width$}")?;
+                }
+            } else {
+                write!(f, "{:width$}", entry.pattern, width = pattern_widths[0])?;
+            }
+            for factor in &entry.factors {
+                write!(f, "{:>FACTOR_WIDTH$}", factor.to_string())?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bandit::*;
+    use crate::money::Money;
+
+    #[test]
+    fn test_pay_tables_match_calc() {
+        let unit = Money::new(5);
+
+        // "Fountains of Fortune" の各行は、その役の賞金と一致する。
+        let table = bandit_fof_pay_table();
+        assert_eq!(table.entries.len(), BanditFofRule::all().len());
+        for (entry, rule) in table.entries.iter().zip(BanditFofRule::all()) {
+            assert_eq!(entry.pattern, rule.label());
+            for (i, factor) in entry.factors.iter().enumerate() {
+                let bet = Bet::new(BanditMachine::Fof, unit, i + 1).unwrap();
+                let payout = rule.prize().calc(bet).unwrap();
+                match *factor {
+                    PayFactor::Times(factor) => assert_eq!(payout, Money::new(5 * factor)),
+                    PayFactor::Jackpot => assert!(rule.prize().is_jackpot() && i == 2),
+                }
+            }
+        }
+
+        let text = bandit_fof_pay_table().to_string();
+        assert!(text.starts_with("Fountains of Fortune\n"));
+        assert_eq!(text.lines().count(), 2 + BanditFofRule::all().len());
+        assert!(text.contains("JACKPOT"));
+
+        // リールの位置で決まる役は、リールごとの列に並ぶ。
+        let text = bandit_ror_pay_table().to_string();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines.len(), 2 + BanditRorRule::all().len());
+        assert!(lines[1].starts_with("REEL 1"));
+        let reel_2 = lines[1].find("REEL 2").unwrap();
+        let reel_3 = lines[1].find("REEL 3").unwrap();
+        let last = lines.last().unwrap();
+        assert!(last.starts_with("CHERRY"));
+        assert_eq!(&last[reel_2..][..3], "ANY");
+        assert_eq!(&last[reel_3..][..3], "ANY");
+        assert!(last.ends_with(" 2"));
+    }
+}
@@ -1,7 +1,10 @@
-use std::num::NonZeroUsize;
+use crate::money::Money;
+
+use super::bet::{BanditMachine, Bet, BetError};
+use super::pay_table::{PayFactor, PayTable, PayTableEntry};
 
 /// スロットマシン "Riches of Rome" のプレイ結果。
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct BanditRorPrize {
     factors: [u32; 3],
 }
@@ -16,31 +19,96 @@ impl BanditRorPrize {
         self.factors
     }
 
-    pub fn calc(self, bet_unit: u32, bet_count: NonZeroUsize) -> u32 {
-        self.factors
+    pub fn calc(self, bet: Bet) -> Result<Money, BetError> {
+        bet.expect_machine(BanditMachine::Ror)?;
+
+        let payout = self
+            .factors
             .into_iter()
-            .take(bet_count.get())
-            .map(|factor| bet_unit * factor)
-            .sum()
+            .take(bet.count().get())
+            .try_fold(Money::ZERO, |sum, factor| {
+                sum.checked_add(bet.unit().checked_mul(factor)?)
+            })?;
+        Ok(payout)
     }
 }
 
 /// スロットマシン "Riches of Rome" を指定した乱数列でプレイし、結果を返す。
 pub fn bandit_ror_play(rs: [u8; 3]) -> BanditRorPrize {
-    let reels = randoms_to_reels(rs);
+    play_with(&REEL_BASES, &SYMBOL_TABLES, &FACTORS, rs)
+}
+
+/// スロットマシン "Riches of Rome" の配当表。倍率は 1 ライン (BET 1 枚) あたり。
+pub fn bandit_ror_pay_table() -> PayTable {
+    pay_table_with(&FACTORS)
+}
+
+/// スロットマシン "Riches of Rome" を指定した乱数列でプレイし、各行のシンボルを返す。
+///
+/// 行は (中央行, 上行, 下行) の順 (BET 順と同じ)。
+pub fn bandit_ror_lines(rs: [u8; 3]) -> [[BanditRorSymbol; 3]; 3] {
+    lines_with(&REEL_BASES, &SYMBOL_TABLES, rs)
+}
 
-    let factors = std::array::from_fn(|i| {
+pub(crate) fn play_with(
+    reel_bases: &[u8; 3],
+    symbol_tables: &[[BanditRorSymbol; 20]; 3],
+    factors: &[u32; 8],
+    rs: [u8; 3],
+) -> BanditRorPrize {
+    let lines = lines_with(reel_bases, symbol_tables, rs);
+    let factors = lines.map(|syms| calc_rule(syms).map_or(0, |rule| factors[rule.rank()]));
+
+    BanditRorPrize::new(factors)
+}
+
+pub(crate) fn lines_with(
+    reel_bases: &[u8; 3],
+    symbol_tables: &[[BanditRorSymbol; 20]; 3],
+    rs: [u8; 3],
+) -> [[BanditRorSymbol; 3]; 3] {
+    let reels = randoms_to_reels(reel_bases, rs);
+
+    std::array::from_fn(|i| {
         const BIASS: [u8; 3] = [2, 0, 4];
         let reels = reels.map(|reel| reel.wrapping_add(BIASS[i]));
-        let syms = reels_to_symbols(reels);
-        prize_factor(syms)
-    });
+        reels_to_symbols(symbol_tables, reels)
+    })
+}
 
-    BanditRorPrize::new(factors)
+pub(crate) fn pay_table_with(factors: &[u32; 8]) -> PayTable {
+    let entries = BanditRorRule::all()
+        .into_iter()
+        .map(|rule| PayTableEntry {
+            pattern: rule.label(),
+            reels: rule.reel_symbols().map(reel_label).to_vec(),
+            factors: vec![PayFactor::Times(factors[rule.rank()])],
+        })
+        .collect();
+
+    PayTable {
+        machine: BanditMachine::Ror,
+        entries,
+    }
+}
+
+/// 配当表の 1 リール分の表記。全てのシンボルが対象なら "ANY"。
+fn reel_label(syms: &[BanditRorSymbol]) -> String {
+    if syms.len() == ANY.len() {
+        return "ANY".to_owned();
+    }
+    syms.iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("/")
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-enum Symbol {
+/// 役ごとの賞金倍率。`BanditRorRule::all()` の順。
+pub(crate) const FACTORS: [u32; 8] = [200, 100, 100, 18, 14, 10, 5, 2];
+
+/// スロットマシン "Riches of Rome" のシンボル。
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum BanditRorSymbol {
     Watermelon,
     Lemon,
     Bar,
@@ -51,51 +119,202 @@ enum Symbol {
     Bell,
 }
 
-fn randoms_to_reels(rs: [u8; 3]) -> [u8; 3] {
-    const BASES: [u8; 3] = [40, 10, 24];
+impl std::fmt::Display for BanditRorSymbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use BanditRorSymbol::*;
 
-    // 乱数は逆順に適用される。
-    std::array::from_fn(|i| 2 * (BASES[i].wrapping_add(rs[2 - i]) % 20))
+        let s = match self {
+            Watermelon => "WATERMELON",
+            Lemon => "LEMON",
+            Bar => "BAR",
+            Seven => "7",
+            Cherry => "CHERRY",
+            Orange => "ORANGE",
+            Plum => "PLUM",
+            Bell => "BELL",
+        };
+        f.write_str(s)
+    }
 }
 
-fn reels_to_symbols(reels: [u8; 3]) -> [Symbol; 3] {
-    #[rustfmt::skip]
-    const TABLE: [[Symbol; 20]; 3] = {
-        use Symbol::*;
+/// 全てのシンボル。
+const ANY: &[BanditRorSymbol] = {
+    use BanditRorSymbol::*;
+    &[Watermelon, Lemon, Bar, Seven, Cherry, Orange, Plum, Bell]
+};
+
+/// スロットマシン "Riches of Rome" の役 (1 ライン上のシンボルの組み合わせ)。
+///
+/// 上から順に判定され、最初に成立したものが採用される。判定順は賞金の高い順でもある。
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum BanditRorRule {
+    ThreeSevens,
+    ThreeBars,
+    Watermelons,
+    Bells,
+    Plums,
+    Oranges,
+    TwoCherries,
+    OneCherry,
+}
+
+impl BanditRorRule {
+    /// 判定順に全ての役を返す。
+    pub const fn all() -> [Self; 8] {
+        use BanditRorRule::*;
+
         [
-            [
-                Orange, Watermelon, Plum,   Cherry, Plum,       Orange, Seven,  Bell, Orange, Cherry,
-                Bar,    Plum,       Orange, Plum,   Watermelon, Plum,   Orange, Plum, Bar,    Plum,
-            ],
-            [
-                Cherry, Plum,   Cherry, Seven, Cherry, Bell, Bar,    Bell,   Cherry, Orange,
-                Bell,   Orange, Plum,   Bell,  Cherry, Bar,  Orange, Cherry, Bell,   Watermelon,
-            ],
-            [
-                Lemon, Orange, Plum, Bell, Orange, Lemon, Bar,  Watermelon, Bell,  Plum,
-                Lemon, Bell,   Plum, Bell, Seven,  Lemon, Bell, Orange,     Lemon, Bar,
-            ],
+            ThreeSevens,
+            ThreeBars,
+            Watermelons,
+            Bells,
+            Plums,
+            Oranges,
+            TwoCherries,
+            OneCherry,
         ]
-    };
+    }
+
+    pub const fn label(self) -> &'static str {
+        use BanditRorRule::*;
+
+        match self {
+            ThreeSevens => "three sevens",
+            ThreeBars => "three bars",
+            Watermelons => "two watermelons and watermelon/bar",
+            Bells => "two bells and bell/bar",
+            Plums => "two plums and plum/bar",
+            Oranges => "two oranges and orange/bar",
+            TwoCherries => "two cherries",
+            OneCherry => "one cherry",
+        }
+    }
+
+    /// 各リール (左から) で対象となるシンボル。
+    pub const fn reel_symbols(self) -> [&'static [BanditRorSymbol]; 3] {
+        use BanditRorRule::*;
+        use BanditRorSymbol::*;
+
+        match self {
+            ThreeSevens => [&[Seven]; 3],
+            ThreeBars => [&[Bar]; 3],
+            Watermelons => [&[Watermelon], &[Watermelon], &[Watermelon, Bar]],
+            Bells => [&[Bell], &[Bell], &[Bell, Bar]],
+            Plums => [&[Plum], &[Plum], &[Plum, Bar]],
+            Oranges => [&[Orange], &[Orange], &[Orange, Bar]],
+            TwoCherries => [&[Cherry], &[Cherry], ANY],
+            OneCherry => [&[Cherry], ANY, ANY],
+        }
+    }
+
+    /// ライン上のシンボルがこの役を満たすか。
+    pub fn matches(self, syms: [BanditRorSymbol; 3]) -> bool {
+        syms.iter()
+            .zip(self.reel_symbols())
+            .all(|(sym, targets)| targets.contains(sym))
+    }
+
+    /// 賞金倍率テーブルの行 (判定順の番号)。
+    pub(crate) fn rank(self) -> usize {
+        self as usize
+    }
+}
+
+impl std::fmt::Display for BanditRorRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// 各リールの乱数に加算される基準値。
+pub(crate) const REEL_BASES: [u8; 3] = [40, 10, 24];
+
+/// 乱数列から各リールの停止位置を求める。
+pub(crate) fn randoms_to_reels(reel_bases: &[u8; 3], rs: [u8; 3]) -> [u8; 3] {
+    // 乱数は逆順に適用される。
+    std::array::from_fn(|i| 2 * (reel_bases[i].wrapping_add(rs[2 - i]) % 20))
+}
+
+/// リール位置からシンボルへの変換テーブル。
+#[rustfmt::skip]
+pub(crate) const SYMBOL_TABLES: [[BanditRorSymbol; 20]; 3] = {
+    use BanditRorSymbol::*;
+    [
+        [
+            Orange, Watermelon, Plum,   Cherry, Plum,       Orange, Seven,  Bell, Orange, Cherry,
+            Bar,    Plum,       Orange, Plum,   Watermelon, Plum,   Orange, Plum, Bar,    Plum,
+        ],
+        [
+            Cherry, Plum,   Cherry, Seven, Cherry, Bell, Bar,    Bell,   Cherry, Orange,
+            Bell,   Orange, Plum,   Bell,  Cherry, Bar,  Orange, Cherry, Bell,   Watermelon,
+        ],
+        [
+            Lemon, Orange, Plum, Bell, Orange, Lemon, Bar,  Watermelon, Bell,  Plum,
+            Lemon, Bell,   Plum, Bell, Seven,  Lemon, Bell, Orange,     Lemon, Bar,
+        ],
+    ]
+};
 
+fn reels_to_symbols(
+    symbol_tables: &[[BanditRorSymbol; 20]; 3],
+    reels: [u8; 3],
+) -> [BanditRorSymbol; 3] {
     std::array::from_fn(|i| {
         let idx = usize::from(reels[i] % 40 / 2);
-        TABLE[i][idx]
+        symbol_tables[i][idx]
     })
 }
 
-fn prize_factor(syms: [Symbol; 3]) -> u32 {
-    use Symbol::*;
+/// 成立した役を返す。
+fn calc_rule(syms: [BanditRorSymbol; 3]) -> Option<BanditRorRule> {
+    BanditRorRule::all()
+        .into_iter()
+        .find(|rule| rule.matches(syms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules() {
+        use BanditRorSymbol::*;
+
+        let factor = |syms| calc_rule(syms).map_or(0, |rule| FACTORS[rule.rank()]);
+        assert_eq!(factor([Seven, Seven, Seven]), 200);
+        assert_eq!(factor([Bar, Bar, Bar]), 100);
+        assert_eq!(factor([Watermelon, Watermelon, Bar]), 100);
+        assert_eq!(factor([Bell, Bell, Bell]), 18);
+        assert_eq!(factor([Plum, Plum, Bar]), 14);
+        assert_eq!(factor([Orange, Orange, Orange]), 10);
+        assert_eq!(factor([Cherry, Cherry, Seven]), 5);
+        assert_eq!(factor([Cherry, Bar, Bar]), 2);
+
+        // BAR が代わりになるのは右端のリールだけ。
+        assert_eq!(factor([Bar, Watermelon, Watermelon]), 0);
+        assert_eq!(factor([Seven, Seven, Bar]), 0);
+        assert_eq!(factor([Orange, Orange, Plum]), 0);
+        assert_eq!(factor([Lemon, Lemon, Lemon]), 0);
+        assert_eq!(factor([Bell, Cherry, Cherry]), 0);
 
-    match syms {
-        [Seven, Seven, Seven] => 200,
-        [Bar, Bar, Bar] => 100,
-        [Watermelon, Watermelon, Bar | Watermelon] => 100,
-        [Bell, Bell, Bar | Bell] => 18,
-        [Plum, Plum, Bar | Plum] => 14,
-        [Orange, Orange, Bar | Orange] => 10,
-        [Cherry, Cherry, _] => 5,
-        [Cherry, _, _] => 2,
-        _ => 0,
+        let table = bandit_ror_pay_table();
+        let factors: Vec<_> = table
+            .entries
+            .iter()
+            .map(|entry| entry.factors.clone())
+            .collect();
+        assert_eq!(
+            factors,
+            [200, 100, 100, 18, 14, 10, 5, 2].map(|f| vec![PayFactor::Times(f)])
+        );
+        assert_eq!(
+            table.entries[2].pattern,
+            "two watermelons and watermelon/bar"
+        );
+        assert_eq!(
+            table.entries[2].reels,
+            ["WATERMELON", "WATERMELON", "WATERMELON/BAR"]
+        );
+        assert_eq!(table.entries[7].reels, ["CHERRY", "ANY", "ANY"]);
     }
 }
@@ -0,0 +1,630 @@
+//! テスト専用の 6502 インタプリタ。
+//!
+//! カートリッジの乱数ルーチンを実際に実行し、`Rng` の実装と照合するために使う。
+//! NES の CPU (2A03) を想定し、10 進モードは実装しない。非公式命令も実装しない。
+
+use crate::ram::WorkRam;
+use crate::rng::{Rng, RngRamLayout};
+
+/// CPU から見たメモリ空間。
+pub(crate) trait Bus {
+    fn read(&mut self, addr: u16) -> u8;
+    fn write(&mut self, addr: u16, value: u8);
+}
+
+const FLAG_C: u8 = 1 << 0;
+const FLAG_Z: u8 = 1 << 1;
+const FLAG_I: u8 = 1 << 2;
+const FLAG_D: u8 = 1 << 3;
+const FLAG_B: u8 = 1 << 4;
+const FLAG_U: u8 = 1 << 5;
+const FLAG_V: u8 = 1 << 6;
+const FLAG_N: u8 = 1 << 7;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum CpuError {
+    UnknownOpcode { pc: u16, opcode: u8 },
+    Brk { pc: u16 },
+    StepLimit,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Cpu {
+    pub a: u8,
+    pub x: u8,
+    pub y: u8,
+    pub s: u8,
+    pub p: u8,
+    pub pc: u16,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Mode {
+    Imm,
+    Zp,
+    Zpx,
+    Zpy,
+    Abs,
+    Absx,
+    Absy,
+    Indx,
+    Indy,
+}
+
+impl Cpu {
+    pub(crate) fn new() -> Self {
+        Self {
+            s: 0xFD,
+            p: FLAG_U | FLAG_I,
+            ..Default::default()
+        }
+    }
+
+    /// `addr` のサブルーチンを呼び出し、RTS で戻ってくるまで実行する。
+    pub(crate) fn call(
+        &mut self,
+        bus: &mut impl Bus,
+        addr: u16,
+        step_max: usize,
+    ) -> Result<(), CpuError> {
+        // 戻り先として番兵アドレスを積んでおき、そこへ戻ってきたら終了。
+        const SENTINEL: u16 = 0xFFFF;
+
+        let s_start = self.s;
+        self.push16(bus, SENTINEL.wrapping_sub(1));
+        self.pc = addr;
+
+        for _ in 0..step_max {
+            self.step(bus)?;
+            if self.pc == SENTINEL && self.s == s_start {
+                return Ok(());
+            }
+        }
+
+        Err(CpuError::StepLimit)
+    }
+
+    /// 1 命令実行する。
+    pub(crate) fn step(&mut self, bus: &mut impl Bus) -> Result<(), CpuError> {
+        use Mode::*;
+
+        let pc = self.pc;
+        let opcode = self.fetch(bus);
+
+        match opcode {
+            // ロード/ストア
+            0xA9 | 0xA5 | 0xB5 | 0xAD | 0xBD | 0xB9 | 0xA1 | 0xB1 => {
+                let mode =
+                    [Indx, Zp, Imm, Abs, Indy, Zpx, Absy, Absx][usize::from(opcode >> 2 & 7)];
+                self.a = self.read_operand(bus, mode);
+                self.set_zn(self.a);
+            }
+            0xA2 | 0xA6 | 0xB6 | 0xAE | 0xBE => {
+                let mode = match opcode {
+                    0xA2 => Imm,
+                    0xA6 => Zp,
+                    0xB6 => Zpy,
+                    0xAE => Abs,
+                    _ => Absy,
+                };
+                self.x = self.read_operand(bus, mode);
+                self.set_zn(self.x);
+            }
+            0xA0 | 0xA4 | 0xB4 | 0xAC | 0xBC => {
+                let mode = match opcode {
+                    0xA0 => Imm,
+                    0xA4 => Zp,
+                    0xB4 => Zpx,
+                    0xAC => Abs,
+                    _ => Absx,
+                };
+                self.y = self.read_operand(bus, mode);
+                self.set_zn(self.y);
+            }
+            0x85 | 0x95 | 0x8D | 0x9D | 0x99 | 0x81 | 0x91 => {
+                let mode =
+                    [Indx, Zp, Imm, Abs, Indy, Zpx, Absy, Absx][usize::from(opcode >> 2 & 7)];
+                let addr = self.operand_addr(bus, mode);
+                bus.write(addr, self.a);
+            }
+            0x86 | 0x96 | 0x8E => {
+                let mode = match opcode {
+                    0x86 => Zp,
+                    0x96 => Zpy,
+                    _ => Abs,
+                };
+                let addr = self.operand_addr(bus, mode);
+                bus.write(addr, self.x);
+            }
+            0x84 | 0x94 | 0x8C => {
+                let mode = match opcode {
+                    0x84 => Zp,
+                    0x94 => Zpx,
+                    _ => Abs,
+                };
+                let addr = self.operand_addr(bus, mode);
+                bus.write(addr, self.y);
+            }
+
+            // 演算 (ORA, AND, EOR, ADC, CMP, SBC)
+            op if op & 3 == 1 && !matches!(op >> 5, 4 | 5) => {
+                let mode = [Indx, Zp, Imm, Abs, Indy, Zpx, Absy, Absx][usize::from(op >> 2 & 7)];
+                let value = self.read_operand(bus, mode);
+                match op >> 5 {
+                    0 => self.a |= value,
+                    1 => self.a &= value,
+                    2 => self.a ^= value,
+                    3 => self.adc(value),
+                    6 => self.compare(self.a, value),
+                    _ => self.adc(!value),
+                }
+                if !matches!(op >> 5, 3 | 6 | 7) {
+                    self.set_zn(self.a);
+                }
+            }
+            0xE0 | 0xE4 | 0xEC => {
+                let mode = [Imm, Zp, Imm, Abs][usize::from(opcode >> 2 & 3)];
+                let value = self.read_operand(bus, mode);
+                self.compare(self.x, value);
+            }
+            0xC0 | 0xC4 | 0xCC => {
+                let mode = [Imm, Zp, Imm, Abs][usize::from(opcode >> 2 & 3)];
+                let value = self.read_operand(bus, mode);
+                self.compare(self.y, value);
+            }
+            0x24 | 0x2C => {
+                let mode = if opcode == 0x24 { Zp } else { Abs };
+                let value = self.read_operand(bus, mode);
+                self.set_flag(FLAG_Z, self.a & value == 0);
+                self.set_flag(FLAG_V, value & FLAG_V != 0);
+                self.set_flag(FLAG_N, value & FLAG_N != 0);
+            }
+
+            // シフト/ローテート/INC/DEC (メモリ)
+            0x06 | 0x16 | 0x0E | 0x1E | 0x26 | 0x36 | 0x2E | 0x3E | 0x46 | 0x56 | 0x4E | 0x5E
+            | 0x66 | 0x76 | 0x6E | 0x7E | 0xC6 | 0xD6 | 0xCE | 0xDE | 0xE6 | 0xF6 | 0xEE | 0xFE => {
+                let mode = match opcode & 0x1C {
+                    0x04 => Zp,
+                    0x14 => Zpx,
+                    0x0C => Abs,
+                    _ => Absx,
+                };
+                let addr = self.operand_addr(bus, mode);
+                let value = bus.read(addr);
+                let value = self.rmw(opcode >> 5, value);
+                bus.write(addr, value);
+            }
+            0x0A | 0x2A | 0x4A | 0x6A => {
+                self.a = self.rmw(opcode >> 5, self.a);
+            }
+
+            // レジスタ操作
+            0xAA => self.transfer(self.a, |cpu, v| cpu.x = v),
+            0xA8 => self.transfer(self.a, |cpu, v| cpu.y = v),
+            0x8A => self.transfer(self.x, |cpu, v| cpu.a = v),
+            0x98 => self.transfer(self.y, |cpu, v| cpu.a = v),
+            0xBA => self.transfer(self.s, |cpu, v| cpu.x = v),
+            0x9A => self.s = self.x,
+            0xE8 => self.transfer(self.x.wrapping_add(1), |cpu, v| cpu.x = v),
+            0xC8 => self.transfer(self.y.wrapping_add(1), |cpu, v| cpu.y = v),
+            0xCA => self.transfer(self.x.wrapping_sub(1), |cpu, v| cpu.x = v),
+            0x88 => self.transfer(self.y.wrapping_sub(1), |cpu, v| cpu.y = v),
+
+            // スタック
+            0x48 => self.push(bus, self.a),
+            0x08 => self.push(bus, self.p | FLAG_B | FLAG_U),
+            0x68 => {
+                let value = self.pop(bus);
+                self.transfer(value, |cpu, v| cpu.a = v);
+            }
+            0x28 => self.p = (self.pop(bus) & !FLAG_B) | FLAG_U,
+
+            // フラグ操作
+            0x18 => self.set_flag(FLAG_C, false),
+            0x38 => self.set_flag(FLAG_C, true),
+            0x58 => self.set_flag(FLAG_I, false),
+            0x78 => self.set_flag(FLAG_I, true),
+            0xB8 => self.set_flag(FLAG_V, false),
+            0xD8 => self.set_flag(FLAG_D, false),
+            0xF8 => self.set_flag(FLAG_D, true),
+
+            // 分岐
+            0x10 | 0x30 | 0x50 | 0x70 | 0x90 | 0xB0 | 0xD0 | 0xF0 => {
+                let offset = self.fetch(bus) as i8;
+                let flag = [FLAG_N, FLAG_V, FLAG_C, FLAG_Z][usize::from(opcode >> 6)];
+                let expected = (opcode & 0x20) != 0;
+                if ((self.p & flag) != 0) == expected {
+                    self.pc = self.pc.wrapping_add_signed(i16::from(offset));
+                }
+            }
+
+            // ジャンプ/サブルーチン
+            0x4C => self.pc = self.fetch16(bus),
+            0x6C => {
+                let ptr = self.fetch16(bus);
+                // ページ境界をまたがない (6502 のバグ)。
+                let ptr_hi = (ptr & 0xFF00) | (ptr.wrapping_add(1) & 0x00FF);
+                self.pc = u16::from_le_bytes([bus.read(ptr), bus.read(ptr_hi)]);
+            }
+            0x20 => {
+                let addr = self.fetch16(bus);
+                self.push16(bus, self.pc.wrapping_sub(1));
+                self.pc = addr;
+            }
+            0x60 => self.pc = self.pop16(bus).wrapping_add(1),
+            0x40 => {
+                self.p = (self.pop(bus) & !FLAG_B) | FLAG_U;
+                self.pc = self.pop16(bus);
+            }
+
+            0xEA => {}
+            0x00 => return Err(CpuError::Brk { pc }),
+            _ => return Err(CpuError::UnknownOpcode { pc, opcode }),
+        }
+
+        Ok(())
+    }
+
+    fn adc(&mut self, value: u8) {
+        let carry = u16::from(self.p & FLAG_C);
+        let sum = u16::from(self.a) + u16::from(value) + carry;
+        let result = sum as u8;
+
+        self.set_flag(FLAG_C, sum > 0xFF);
+        self.set_flag(FLAG_V, (!(self.a ^ value) & (self.a ^ result) & 0x80) != 0);
+        self.a = result;
+        self.set_zn(result);
+    }
+
+    fn compare(&mut self, lhs: u8, rhs: u8) {
+        self.set_flag(FLAG_C, lhs >= rhs);
+        self.set_zn(lhs.wrapping_sub(rhs));
+    }
+
+    /// ASL, ROL, LSR, ROR, DEC, INC のいずれかを行う (`kind` は opcode の上位 3 bit)。
+    fn rmw(&mut self, kind: u8, value: u8) -> u8 {
+        let carry_in = self.p & FLAG_C;
+        let (result, carry_out) = match kind {
+            0 => (value << 1, Some(value >> 7)),
+            1 => ((value << 1) | carry_in, Some(value >> 7)),
+            2 => (value >> 1, Some(value & 1)),
+            3 => ((value >> 1) | (carry_in << 7), Some(value & 1)),
+            6 => (value.wrapping_sub(1), None),
+            _ => (value.wrapping_add(1), None),
+        };
+
+        if let Some(carry) = carry_out {
+            self.set_flag(FLAG_C, carry != 0);
+        }
+        self.set_zn(result);
+
+        result
+    }
+
+    fn transfer(&mut self, value: u8, set: impl FnOnce(&mut Self, u8)) {
+        set(self, value);
+        self.set_zn(value);
+    }
+
+    fn read_operand(&mut self, bus: &mut impl Bus, mode: Mode) -> u8 {
+        if let Mode::Imm = mode {
+            return self.fetch(bus);
+        }
+
+        let addr = self.operand_addr(bus, mode);
+        bus.read(addr)
+    }
+
+    fn operand_addr(&mut self, bus: &mut impl Bus, mode: Mode) -> u16 {
+        let zp_ptr = |bus: &mut dyn FnMut(u16) -> u8, zp: u8| -> u16 {
+            u16::from_le_bytes([bus(u16::from(zp)), bus(u16::from(zp.wrapping_add(1)))])
+        };
+
+        match mode {
+            Mode::Imm => unreachable!(),
+            Mode::Zp => u16::from(self.fetch(bus)),
+            Mode::Zpx => u16::from(self.fetch(bus).wrapping_add(self.x)),
+            Mode::Zpy => u16::from(self.fetch(bus).wrapping_add(self.y)),
+            Mode::Abs => self.fetch16(bus),
+            Mode::Absx => self.fetch16(bus).wrapping_add(u16::from(self.x)),
+            Mode::Absy => self.fetch16(bus).wrapping_add(u16::from(self.y)),
+            Mode::Indx => {
+                let zp = self.fetch(bus).wrapping_add(self.x);
+                zp_ptr(&mut |addr| bus.read(addr), zp)
+            }
+            Mode::Indy => {
+                let zp = self.fetch(bus);
+                zp_ptr(&mut |addr| bus.read(addr), zp).wrapping_add(u16::from(self.y))
+            }
+        }
+    }
+
+    fn fetch(&mut self, bus: &mut impl Bus) -> u8 {
+        let value = bus.read(self.pc);
+        self.pc = self.pc.wrapping_add(1);
+        value
+    }
+
+    fn fetch16(&mut self, bus: &mut impl Bus) -> u16 {
+        let lo = self.fetch(bus);
+        let hi = self.fetch(bus);
+        u16::from_le_bytes([lo, hi])
+    }
+
+    fn push(&mut self, bus: &mut impl Bus, value: u8) {
+        bus.write(0x100 | u16::from(self.s), value);
+        self.s = self.s.wrapping_sub(1);
+    }
+
+    fn push16(&mut self, bus: &mut impl Bus, value: u16) {
+        let [lo, hi] = value.to_le_bytes();
+        self.push(bus, hi);
+        self.push(bus, lo);
+    }
+
+    fn pop(&mut self, bus: &mut impl Bus) -> u8 {
+        self.s = self.s.wrapping_add(1);
+        bus.read(0x100 | u16::from(self.s))
+    }
+
+    fn pop16(&mut self, bus: &mut impl Bus) -> u16 {
+        let lo = self.pop(bus);
+        let hi = self.pop(bus);
+        u16::from_le_bytes([lo, hi])
+    }
+
+    fn set_flag(&mut self, flag: u8, on: bool) {
+        if on {
+            self.p |= flag;
+        } else {
+            self.p &= !flag;
+        }
+    }
+
+    fn set_zn(&mut self, value: u8) {
+        self.set_flag(FLAG_Z, value == 0);
+        self.set_flag(FLAG_N, (value & 0x80) != 0);
+    }
+}
+
+/// NES のメモリ空間を単純化したもの。
+///
+/// $8000-$BFFF に PRG の任意の 16 KiB バンク、$C000-$FFFF に最終バンクを配置する。
+/// PPU/APU などのレジスタは読むと 0 を返し、書き込みは無視する。
+pub(crate) struct NesBus<'a> {
+    pub ram: WorkRam,
+    pub sram: [u8; 0x2000],
+    prg_lo: &'a [u8],
+    prg_hi: &'a [u8],
+}
+
+impl<'a> NesBus<'a> {
+    pub(crate) fn new(prg: &'a [u8], bank_lo: usize) -> Self {
+        const BANK_LEN: usize = 0x4000;
+
+        let bank_count = prg.len() / BANK_LEN;
+        assert!(bank_count > 0 && bank_lo < bank_count);
+
+        Self {
+            ram: [0; 0x800],
+            sram: [0; 0x2000],
+            prg_lo: &prg[BANK_LEN * bank_lo..][..BANK_LEN],
+            prg_hi: &prg[BANK_LEN * (bank_count - 1)..][..BANK_LEN],
+        }
+    }
+}
+
+impl Bus for NesBus<'_> {
+    fn read(&mut self, addr: u16) -> u8 {
+        let addr = usize::from(addr);
+        match addr {
+            0x0000..=0x1FFF => self.ram[addr & 0x7FF],
+            0x6000..=0x7FFF => self.sram[addr - 0x6000],
+            0x8000..=0xBFFF => self.prg_lo[addr - 0x8000],
+            0xC000..=0xFFFF => self.prg_hi[addr - 0xC000],
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        let addr = usize::from(addr);
+        match addr {
+            0x0000..=0x1FFF => self.ram[addr & 0x7FF] = value,
+            0x6000..=0x7FFF => self.sram[addr - 0x6000] = value,
+            _ => {}
+        }
+    }
+}
+
+/// 乱数ルーチンと `Rng` の最初の不一致。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct RngDivergence {
+    pub index_start: u8,
+    pub draw: usize,
+    pub expected: Rng,
+    pub actual: Rng,
+}
+
+impl std::fmt::Display for RngDivergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "index_start=0x{:02X}, draw={}: ",
+            self.index_start, self.draw
+        )?;
+
+        if self.expected.index() != self.actual.index() {
+            return write!(
+                f,
+                "index expected 0x{:02X}, actual 0x{:02X}",
+                self.expected.index(),
+                self.actual.index()
+            );
+        }
+
+        let (slot, (e, a)) = self
+            .expected
+            .table()
+            .iter()
+            .zip(self.actual.table())
+            .enumerate()
+            .find(|(_, (e, a))| e != a)
+            .unwrap();
+        write!(f, "table[{slot}] expected 0x{e:02X}, actual 0x{a:02X}")
+    }
+}
+
+/// 全ての開始インデックスについて、`routine` を `draw_count` 回呼び出した結果を `Rng::gen()` と照合する。
+///
+/// 乱数の状態は `layout` に従って RAM に置かれているものとする。最初の不一致を返す。
+pub(crate) fn cross_check_rng_routine(
+    bus: &mut NesBus,
+    routine: u16,
+    layout: RngRamLayout,
+    table_ini: &[u8; 250],
+    draw_count: usize,
+) -> Result<Option<RngDivergence>, CpuError> {
+    const STEP_MAX: usize = 100000;
+
+    for index_start in 0..=0xFF {
+        let mut rng = Rng::from_raw(index_start, table_ini);
+        bus.ram = [0; 0x800];
+        rng.write_ram(&mut bus.ram, layout);
+
+        let mut cpu = Cpu::new();
+        for draw in 0..draw_count {
+            cpu.call(bus, routine, STEP_MAX)?;
+            rng.gen();
+
+            let actual = Rng::from_ram(&bus.ram, layout);
+            if actual != rng {
+                return Ok(Some(RngDivergence {
+                    index_start,
+                    draw,
+                    expected: rng,
+                    actual,
+                }));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ram::RamMap;
+    use crate::rom::tests::load_user_rom;
+    use crate::rom::RomTables;
+
+    use super::*;
+
+    /// $C000 から `program` を置いた PRG を作る。
+    fn program_prg(program: &[u8]) -> Vec<u8> {
+        let mut prg = vec![0xEA; 0x4000];
+        prg[..program.len()].copy_from_slice(program);
+        prg
+    }
+
+    #[test]
+    fn test_cross_check_reports_divergence() {
+        // インデックスを進めるだけでテーブルを書かないルーチン: INC $10; RTS
+        let prg = program_prg(&[0xE6, 0x10, 0x60]);
+        let mut bus = NesBus::new(&prg, 0);
+
+        let layout = RngRamLayout::new(0x10, 0x300).unwrap();
+        let table_ini = RomTables::default().rng_table_ini;
+
+        let divergence = cross_check_rng_routine(&mut bus, 0xC000, layout, &table_ini, 10)
+            .unwrap()
+            .unwrap();
+        assert_eq!(divergence.index_start, 0);
+        assert_eq!(divergence.draw, 0);
+        assert_eq!(divergence.actual.index(), divergence.expected.index());
+        assert!(divergence
+            .to_string()
+            .starts_with("index_start=0x00, draw=0: table[103] "));
+    }
+
+    #[test]
+    fn test_cpu_branch_indexed() {
+        // LDX #3; loop: LDA $0300,X; STA $0310,X; DEX; BPL loop; RTS
+        let program = [
+            0xA2, 0x03, 0xBD, 0x00, 0x03, 0x9D, 0x10, 0x03, 0xCA, 0x10, 0xF7, 0x60,
+        ];
+        let prg = program_prg(&program);
+        let mut bus = NesBus::new(&prg, 0);
+        bus.ram[0x300..][..4].copy_from_slice(&[1, 2, 3, 4]);
+
+        let mut cpu = Cpu::new();
+        cpu.call(&mut bus, 0xC000, 100).unwrap();
+
+        assert_eq!(bus.ram[0x310..][..4], [1, 2, 3, 4]);
+        assert_eq!(cpu.x, 0xFF);
+        assert_eq!(cpu.p & FLAG_N, FLAG_N);
+    }
+
+    #[test]
+    fn test_cpu_arith() {
+        // LDA #$50; ADC #$50 (V=1); SEC; SBC #$F0; ASL A; ROR A; RTS
+        let prg = program_prg(&[0xA9, 0x50, 0x69, 0x50, 0x38, 0xE9, 0xF0, 0x0A, 0x6A, 0x60]);
+        let mut bus = NesBus::new(&prg, 0);
+
+        let mut cpu = Cpu::new();
+        cpu.call(&mut bus, 0xC000, 100).unwrap();
+
+        // 0x50 + 0x50 = 0xA0, 0xA0 - 0xF0 = 0xB0 (借りあり), 0xB0 << 1 = 0x60 (C=1), ROR -> 0xB0
+        assert_eq!(cpu.a, 0xB0);
+        assert_eq!(cpu.p & FLAG_C, 0);
+        assert_eq!(cpu.p & FLAG_N, FLAG_N);
+        assert_eq!(cpu.s, 0xFD);
+    }
+
+    /// ROM の乱数ルーチンを `Rng` と照合する。
+    ///
+    /// 環境変数 `CAESARS_PALACE_ROM` に ROM を指定して `--ignored` で実行する。
+    /// ルーチンの位置は `KNOWN_DUMPS` の記録か、環境変数 `CAESARS_PALACE_RNG_ROUTINE` ("BANK:ADDR"、16 進)、
+    /// 乱数の RAM 配置は `RamMap::game()` か、環境変数 `CAESARS_PALACE_RAM_MAP` の配置ファイルを使う。
+    #[test]
+    #[ignore]
+    fn test_cross_check_user_rom() {
+        let rom = load_user_rom().expect("CAESARS_PALACE_ROM is not set");
+
+        let (bank, routine) = match std::env::var("CAESARS_PALACE_RNG_ROUTINE") {
+            Ok(s) => {
+                let (bank, addr) = s.split_once(':').expect("expected BANK:ADDR");
+                (
+                    usize::from_str_radix(bank, 16).expect("invalid bank"),
+                    u16::from_str_radix(addr, 16).expect("invalid address"),
+                )
+            }
+            Err(_) => {
+                let dump = rom.known_dump().unwrap_or_else(|| {
+                    panic!(
+                        "unknown ROM dump (CRC32: {:08X}); set CAESARS_PALACE_RNG_ROUTINE",
+                        rom.crc32()
+                    )
+                });
+                (dump.rng_routine_bank, dump.rng_routine)
+            }
+        };
+        let map = match std::env::var_os("CAESARS_PALACE_RAM_MAP") {
+            Some(path) => std::fs::read_to_string(path)
+                .unwrap()
+                .parse::<RamMap>()
+                .unwrap(),
+            None => {
+                RamMap::game().expect("the game's RAM map is not known; set CAESARS_PALACE_RAM_MAP")
+            }
+        };
+
+        let mut bus = NesBus::new(rom.prg(), bank);
+        let table_ini = RomTables::default().rng_table_ini;
+
+        match cross_check_rng_routine(&mut bus, routine, map.rng, &table_ini, 1000) {
+            Ok(None) => {}
+            Ok(Some(divergence)) => panic!("RNG diverges: {divergence}"),
+            Err(e) => panic!("CPU error: {e:?}"),
+        }
+    }
+}
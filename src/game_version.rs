@@ -0,0 +1,340 @@
+//! カートリッジのバージョン (リージョンやリビジョン) ごとに異なりうるデータ。
+
+use crate::bandit::*;
+use crate::bandit::{distribution, fountains_of_fortune, magnicent_sevens, riches_of_rome};
+use crate::identify;
+use crate::money::Money;
+use crate::rng::Rng;
+use crate::rom::{KnownDump, Rom, RomError, RomTables};
+
+/// ゲームのバージョン。
+///
+/// `Default` は本クレートが持つ (書き写した) テーブルたちからなる。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GameVersion {
+    pub name: String,
+    pub tables: RomTables,
+    pub pay_tables: PayTables,
+}
+
+impl Default for GameVersion {
+    fn default() -> Self {
+        Self {
+            name: "default".to_owned(),
+            tables: RomTables::default(),
+            pay_tables: PayTables::default(),
+        }
+    }
+}
+
+impl GameVersion {
+    /// ROM を既知のダンプとして同定し、バージョンを作る。
+    pub fn from_rom(rom: &Rom) -> Result<Self, RomError> {
+        let dump = rom.known_dump().ok_or(RomError::UnknownDump(rom.crc32()))?;
+        Self::from_dump(rom, dump)
+    }
+
+    /// ダンプ `dump` に記録された配置で ROM からテーブルたちを抽出し、バージョンを作る。
+    /// 賞金倍率はダンプに記録されたものを使う。
+    pub fn from_dump(rom: &Rom, dump: &KnownDump) -> Result<Self, RomError> {
+        Ok(Self {
+            name: dump.name.to_owned(),
+            tables: rom.extract_tables(&dump.layout)?,
+            pay_tables: dump.pay_tables.clone(),
+        })
+    }
+
+    /// このバージョンの初期テーブルと指定したインデックスで乱数生成器を作る。
+    pub fn rng(&self, index: u8) -> Rng {
+        Rng::from_raw(index, &self.tables.rng_table_ini)
+    }
+
+    /// `bandit_fof_play()` のこのバージョン版。
+    pub fn bandit_fof_play(&self, rs: [u8; 4]) -> BanditFofPrize {
+        fountains_of_fortune::play_with(&self.tables.fof_reels, &self.tables.fof_symbols, rs)
+    }
+
+    /// `bandit_fof_symbols()` のこのバージョン版。
+    pub fn bandit_fof_symbols(&self, rs: [u8; 4]) -> [BanditFofSymbol; 4] {
+        fountains_of_fortune::symbols_with(&self.tables.fof_reels, &self.tables.fof_symbols, rs)
+    }
+
+    /// `bandit_fof_play_detail()` のこのバージョン版。
+    pub fn bandit_fof_play_detail(&self, rs: [u8; 4]) -> BanditFofDetail {
+        BanditFofDetail::with_table(
+            &self.pay_tables.fof,
+            &self.pay_tables.fof_jackpot_seeds,
+            self.bandit_fof_symbols(rs),
+        )
+    }
+
+    /// `BanditFofPrize::calc()` のこのバージョン版。
+    pub fn bandit_fof_calc(&self, prize: BanditFofPrize, bet: Bet) -> Result<Money, BetError> {
+        prize.calc_with(
+            &self.pay_tables.fof,
+            &self.pay_tables.fof_jackpot_seeds,
+            bet,
+        )
+    }
+
+    /// `bandit_ms_play()` のこのバージョン版。
+    pub fn bandit_ms_play(&self, rs: [u8; 3]) -> BanditMsPrize {
+        magnicent_sevens::play_with(
+            &self.tables.ms_reels,
+            &self.tables.ms_symbols,
+            &self.pay_tables.ms,
+            rs,
+        )
+    }
+
+    /// `bandit_ms_lines()` のこのバージョン版。
+    pub fn bandit_ms_lines(&self, rs: [u8; 3]) -> [[BanditMsSymbol; 3]; 3] {
+        magnicent_sevens::lines_with(&self.tables.ms_reels, &self.tables.ms_symbols, rs)
+    }
+
+    /// `bandit_ror_play()` のこのバージョン版。
+    pub fn bandit_ror_play(&self, rs: [u8; 3]) -> BanditRorPrize {
+        riches_of_rome::play_with(
+            &self.tables.ror_reel_bases,
+            &self.tables.ror_symbols,
+            &self.pay_tables.ror,
+            rs,
+        )
+    }
+
+    /// `bandit_ror_lines()` のこのバージョン版。
+    pub fn bandit_ror_lines(&self, rs: [u8; 3]) -> [[BanditRorSymbol; 3]; 3] {
+        riches_of_rome::lines_with(&self.tables.ror_reel_bases, &self.tables.ror_symbols, rs)
+    }
+
+    /// 指定したスロットマシンの、このバージョンの配当表。
+    pub fn bandit_pay_table(&self, machine: BanditMachine) -> PayTable {
+        match machine {
+            BanditMachine::Fof => fountains_of_fortune::pay_table_with(&self.pay_tables.fof),
+            BanditMachine::Ms => magnicent_sevens::pay_table_with(&self.pay_tables.ms),
+            BanditMachine::Ror => riches_of_rome::pay_table_with(&self.pay_tables.ror),
+        }
+    }
+
+    /// `bandit_fof_distribution()` のこのバージョン版。
+    pub fn bandit_fof_distribution(&self) -> PrizeDistribution<BanditFofPrize> {
+        distribution::fof_distribution_with(self)
+    }
+
+    /// `bandit_ms_distribution()` のこのバージョン版。
+    pub fn bandit_ms_distribution(&self) -> PrizeDistribution<BanditMsPrize> {
+        distribution::ms_distribution_with(self)
+    }
+
+    /// `bandit_ror_distribution()` のこのバージョン版。
+    pub fn bandit_ror_distribution(&self) -> PrizeDistribution<BanditRorPrize> {
+        distribution::ror_distribution_with(self)
+    }
+
+    /// `bandit_fof_distribution_rng()` のこのバージョン版。
+    pub fn bandit_fof_distribution_rng(&self, rng: &Rng) -> PrizeDistribution<BanditFofPrize> {
+        distribution::fof_distribution_rng_with(self, rng)
+    }
+
+    /// `bandit_ms_distribution_rng()` のこのバージョン版。
+    pub fn bandit_ms_distribution_rng(&self, rng: &Rng) -> PrizeDistribution<BanditMsPrize> {
+        distribution::ms_distribution_rng_with(self, rng)
+    }
+
+    /// `bandit_ror_distribution_rng()` のこのバージョン版。
+    pub fn bandit_ror_distribution_rng(&self, rng: &Rng) -> PrizeDistribution<BanditRorPrize> {
+        distribution::ror_distribution_rng_with(self, rng)
+    }
+
+    /// `identify_bandit_fof_index()` のこのバージョン版。
+    pub fn identify_bandit_fof_index(&self, rng: &Rng, symbols: &[BanditFofSymbol; 4]) -> Vec<u8> {
+        identify::identify_by(rng, 4, |rs| {
+            self.bandit_fof_symbols(rs.try_into().unwrap()) == *symbols
+        })
+    }
+
+    /// `identify_bandit_ms_index()` のこのバージョン版。
+    pub fn identify_bandit_ms_index(&self, rng: &Rng, lines: &[[BanditMsSymbol; 3]; 3]) -> Vec<u8> {
+        identify::identify_by(rng, 3, |rs| {
+            self.bandit_ms_lines(rs.try_into().unwrap()) == *lines
+        })
+    }
+
+    /// `identify_bandit_ror_index()` のこのバージョン版。
+    pub fn identify_bandit_ror_index(
+        &self,
+        rng: &Rng,
+        lines: &[[BanditRorSymbol; 3]; 3],
+    ) -> Vec<u8> {
+        identify::identify_by(rng, 3, |rs| {
+            self.bandit_ror_lines(rs.try_into().unwrap()) == *lines
+        })
+    }
+}
+
+/// 各スロットマシンの賞金倍率。
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PayTables {
+    /// "Fountains of Fortune" の役ごと、BET 枚数ごとの賞金倍率。
+    pub fof: [[u32; 3]; 8],
+    /// "Fountains of Fortune" の 3 枚賭けのジャックポットの最小金額 (緑 7, 黒 7 の順)。
+    pub fof_jackpot_seeds: [u32; 2],
+    /// "Magnificent Sevens" の役ごとの賞金倍率。
+    pub ms: [u32; 5],
+    /// "Riches of Rome" の役ごとの賞金倍率。
+    pub ror: [u32; 8],
+}
+
+impl Default for PayTables {
+    fn default() -> Self {
+        Self {
+            fof: fountains_of_fortune::FACTOR_TABLE,
+            fof_jackpot_seeds: fountains_of_fortune::JACKPOT_SEEDS,
+            ms: magnicent_sevens::FACTORS,
+            ror: riches_of_rome::FACTORS,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_version_matches_free_functions() {
+        let version = GameVersion::default();
+        let bet = Bet::new(BanditMachine::Fof, Money::new(5), 2).unwrap();
+
+        for index in 0..=0xFF {
+            let mut rng = version.rng(index);
+            assert_eq!(rng, Rng::with_index(index));
+
+            let rs4: [u8; 4] = rng.gen_array();
+            let rs3 = [rs4[0], rs4[1], rs4[2]];
+
+            let prize = version.bandit_fof_play(rs4);
+            assert_eq!(prize, bandit_fof_play(rs4));
+            assert_eq!(version.bandit_fof_calc(prize, bet), prize.calc(bet));
+            assert_eq!(version.bandit_ms_play(rs3), bandit_ms_play(rs3));
+            assert_eq!(version.bandit_ror_play(rs3), bandit_ror_play(rs3));
+            assert_eq!(version.bandit_ms_lines(rs3), bandit_ms_lines(rs3));
+            assert_eq!(version.bandit_ror_lines(rs3), bandit_ror_lines(rs3));
+        }
+    }
+
+    #[test]
+    fn test_custom_pay_table() {
+        let mut version = GameVersion::default();
+        version.pay_tables.ror = [1; 8];
+
+        let mut rng = Rng::new();
+        let found = (0..10000).any(|_| {
+            let rs = rng.gen_array();
+            let factors = version.bandit_ror_play(rs).factors();
+            factors.contains(&1) && !bandit_ror_play(rs).factors().contains(&1)
+        });
+        assert!(found);
+
+        let mut version = GameVersion::default();
+        let rank = BanditFofRule::ThreeGreenSevens.prize().rank().unwrap();
+        version.pay_tables.fof[rank] = [7, 8, 9];
+
+        let rs = std::iter::repeat_with(|| rng.gen_array())
+            .take(10000)
+            .find(|&rs| {
+                version.bandit_fof_play_detail(rs).rule() == Some(BanditFofRule::ThreeGreenSevens)
+            })
+            .unwrap();
+        let detail = version.bandit_fof_play_detail(rs);
+        assert_eq!(
+            detail.payout_row(Money::new(5)),
+            Ok([35, 40, 45].map(Money::new))
+        );
+        assert_ne!(
+            detail.payout_row(Money::new(5)),
+            bandit_fof_play_detail(rs).payout_row(Money::new(5))
+        );
+    }
+
+    #[test]
+    fn test_version_distribution() {
+        // 倍率を全て 2 倍にすれば、1 枚賭けの賞金の期待値も 2 倍になる。
+        let mut version = GameVersion::default();
+        version.pay_tables.fof = fountains_of_fortune::FACTOR_TABLE.map(|row| row.map(|f| 2 * f));
+
+        let bet = Bet::new(BanditMachine::Fof, Money::new(5), 1).unwrap();
+        let expected = bandit_fof_distribution().expected_payout(bet).unwrap();
+        let dist = version.bandit_fof_distribution();
+        assert_eq!(dist.expected_payout(bet), Ok(2.0 * expected));
+
+        let rng = Rng::new();
+        let expected = bandit_fof_distribution_rng(&rng)
+            .expected_payout(bet)
+            .unwrap();
+        let dist = version.bandit_fof_distribution_rng(&rng);
+        assert_eq!(dist.expected_payout(bet), Ok(2.0 * expected));
+
+        // シンボルテーブルを入れ替えると、同じ観測を生じるインデックスも変わる。
+        let mut version = GameVersion::default();
+        version.tables.fof_symbols.swap(0, 1);
+        let symbols = version.bandit_fof_symbols(Rng::with_index(77).gen_array());
+        let indices = version.identify_bandit_fof_index(&rng, &symbols);
+        assert!(indices.contains(&77));
+        assert_ne!(indices, crate::identify_bandit_fof_index(&rng, &symbols));
+    }
+
+    #[test]
+    fn test_from_dump() {
+        use crate::rom::RomLayout;
+
+        let tables = RomTables {
+            ror_reel_bases: [0, 0, 0],
+            ..Default::default()
+        };
+        let pay_tables = PayTables {
+            fof_jackpot_seeds: [1000, 200000],
+            ..Default::default()
+        };
+
+        let dump = KnownDump {
+            name: "test",
+            crc32: 0,
+            layout: RomLayout {
+                rng_table_ini: 0x0000,
+                fof_reels: [0x0100, 0x0140],
+                fof_symbols: [0x0180, 0x0190],
+                ms_reels: [0x0200, 0x0250, 0x02A0],
+                ms_symbols: 0x0300,
+                ror_reel_bases: [0x0320, 0x0321, 0x0322],
+                ror_symbols: [0x0340, 0x0360, 0x0380],
+            },
+            pay_tables,
+            rng_routine: 0xC000,
+            rng_routine_bank: 0,
+        };
+
+        let mut bytes = b"NES\x1A\x01\x00\0\0\0\0\0\0\0\0\0\0".to_vec();
+        let mut prg = vec![0xFF; 0x4000];
+        for ((_, offset), table) in dump.layout.offsets().into_iter().zip(tables.to_bytes()) {
+            prg[offset..][..table.len()].copy_from_slice(&table);
+        }
+        bytes.extend(prg);
+        let rom = Rom::from_bytes(&bytes).unwrap();
+
+        let version = GameVersion::from_dump(&rom, &dump).unwrap();
+        assert_eq!(version.name, "test");
+        assert_eq!(version.tables, tables);
+
+        // 基準値が 0 なら、乱数 0 で中央行は各リールの 2 番目のシンボルになる。
+        let ror = riches_of_rome::SYMBOL_TABLES;
+        let center = version.bandit_ror_lines([0; 3])[0];
+        assert_eq!(center, [ror[0][1], ror[1][1], ror[2][1]]);
+        assert_ne!(center, bandit_ror_lines([0; 3])[0]);
+
+        let bet = Bet::new(BanditMachine::Fof, Money::new(5), 3).unwrap();
+        assert_eq!(
+            version.bandit_fof_calc(BanditFofPrize::AllBlackSeven, bet),
+            Ok(Money::new(200000))
+        );
+    }
+}
@@ -0,0 +1,137 @@
+//! 画面上で観測した結果から乱数インデックスを特定する。
+//!
+//! スロットマシンのリールは本クレートのテーブルを使う。他のバージョンでは `GameVersion` の同名のメソッドを使う。
+
+use crate::bandit::*;
+use crate::game_version::GameVersion;
+use crate::rng::Rng;
+use crate::trump::{Card, Deck};
+
+/// ポーカーで配られたカードたち (配られた順) を生じうる乱数インデックスを全て返す。
+///
+/// `rng` のテーブルは現在の状態のものを使う (インデックスは無視される)。
+pub fn identify_poker_index(rng: &Rng, cards: &[Card]) -> Vec<u8> {
+    identify_by(rng, cards.len(), |rs| {
+        let mut deck = Deck::new();
+        rs.iter().zip(cards).all(|(&r, &card)| deck.deal(r) == card)
+    })
+}
+
+/// スロットマシン "Fountains of Fortune" のライン上のシンボルを生じうる乱数インデックスを全て返す。
+pub fn identify_bandit_fof_index(rng: &Rng, symbols: &[BanditFofSymbol; 4]) -> Vec<u8> {
+    GameVersion::default().identify_bandit_fof_index(rng, symbols)
+}
+
+/// スロットマシン "Magnificent Sevens" の各行のシンボルを生じうる乱数インデックスを全て返す。
+///
+/// 行は (中央行, 上行, 下行) の順。
+pub fn identify_bandit_ms_index(rng: &Rng, lines: &[[BanditMsSymbol; 3]; 3]) -> Vec<u8> {
+    GameVersion::default().identify_bandit_ms_index(rng, lines)
+}
+
+/// スロットマシン "Riches of Rome" の各行のシンボルを生じうる乱数インデックスを全て返す。
+///
+/// 行は (中央行, 上行, 下行) の順。
+pub fn identify_bandit_ror_index(rng: &Rng, lines: &[[BanditRorSymbol; 3]; 3]) -> Vec<u8> {
+    GameVersion::default().identify_bandit_ror_index(rng, lines)
+}
+
+/// 全ての乱数インデックスについて `len` 個の乱数を生成し、`pred` を満たすものを返す。
+pub(crate) fn identify_by(rng: &Rng, len: usize, mut pred: impl FnMut(&[u8]) -> bool) -> Vec<u8> {
+    let mut rng = rng.clone();
+    let mut rs = Vec::with_capacity(len);
+
+    (0..=0xFF)
+        .filter(|&index| {
+            rng.set_index(index);
+            rs.clear();
+            rs.extend(rng.gen_iter().take(len));
+            rng.undo(index, len);
+
+            pred(&rs)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 各インデックスから新たに複製した乱数生成器で観測し、`observed` と一致するものを返す。
+    fn naive_indices<T: PartialEq>(
+        rng: &Rng,
+        observed: &T,
+        observe: impl Fn(&mut Rng) -> T,
+    ) -> Vec<u8> {
+        (0..=0xFF)
+            .filter(|&index| {
+                let mut rng = rng.clone();
+                rng.set_index(index);
+                observe(&mut rng) == *observed
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_identify_poker_index() {
+        let rng = Rng::new();
+        let deal5 = |rng: &mut Rng| {
+            let mut deck = Deck::new();
+            (0..5).map(|_| deck.deal(rng.gen())).collect::<Vec<_>>()
+        };
+
+        for index in 0..=0xFF {
+            let mut rng_play = rng.clone();
+            rng_play.set_index(index);
+            let cards = deal5(&mut rng_play);
+
+            let indices = identify_poker_index(&rng, &cards);
+            assert!(indices.contains(&index));
+            assert_eq!(indices, naive_indices(&rng, &cards, deal5));
+        }
+    }
+
+    #[test]
+    fn test_identify_bandit_indices() {
+        use BanditFofSymbol::*;
+
+        let rng = Rng::new();
+        let fof = |rng: &mut Rng| bandit_fof_symbols(rng.gen_array());
+        let ms = |rng: &mut Rng| bandit_ms_lines(rng.gen_array());
+        let ror = |rng: &mut Rng| bandit_ror_lines(rng.gen_array());
+
+        for index in 0..=0xFF {
+            let mut rng_play = rng.clone();
+            rng_play.set_index(index);
+
+            let symbols = fof(&mut rng_play.clone());
+            let indices = identify_bandit_fof_index(&rng, &symbols);
+            assert!(indices.contains(&index));
+            assert_eq!(indices, naive_indices(&rng, &symbols, fof));
+
+            let lines = ms(&mut rng_play.clone());
+            let indices = identify_bandit_ms_index(&rng, &lines);
+            assert!(indices.contains(&index));
+            assert_eq!(indices, naive_indices(&rng, &lines, ms));
+
+            let lines = ror(&mut rng_play.clone());
+            let indices = identify_bandit_ror_index(&rng, &lines);
+            assert!(indices.contains(&index));
+            assert_eq!(indices, naive_indices(&rng, &lines, ror));
+        }
+
+        // 初期テーブルでの具体例。同じ観測を生じるインデックスが複数あることもある。
+        assert_eq!(
+            identify_bandit_fof_index(&rng, &[GreenSeven, GreenSeven, Null, Null]),
+            [249, 252, 253, 254]
+        );
+        let mut rng_play = Rng::with_index(77);
+        assert_eq!(identify_bandit_ms_index(&rng, &ms(&mut rng_play)), [77]);
+        let mut rng_play = Rng::with_index(252);
+        assert_eq!(
+            identify_bandit_ror_index(&rng, &ror(&mut rng_play)),
+            [78, 252]
+        );
+        assert_eq!(identify_bandit_fof_index(&rng, &[Cherry; 4]), []);
+    }
+}
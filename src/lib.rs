@@ -1,7 +1,31 @@
+mod analysis;
 mod bandit;
+#[cfg(test)]
+mod cpu6502;
+mod game_version;
+mod identify;
+mod money;
+mod oracle;
+mod ram;
 mod rng;
+mod rng_deps;
+mod rng_quality;
+mod rom;
+mod savestate;
+mod trace;
 mod trump;
 
+pub use self::analysis::*;
 pub use self::bandit::*;
+pub use self::game_version::*;
+pub use self::identify::*;
+pub use self::money::*;
+pub use self::oracle::*;
+pub use self::ram::*;
 pub use self::rng::*;
+pub use self::rng_deps::*;
+pub use self::rng_quality::*;
+pub use self::rom::*;
+pub use self::savestate::*;
+pub use self::trace::*;
 pub use self::trump::*;
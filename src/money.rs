@@ -0,0 +1,194 @@
+//! 金額。
+
+/// 金額 (ドル)。
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Money(u32);
+
+impl Money {
+    pub const ZERO: Self = Self(0);
+    pub const MAX: Self = Self(u32::MAX);
+
+    pub const fn new(inner: u32) -> Self {
+        Self(inner)
+    }
+
+    pub const fn get(self) -> u32 {
+        self.0
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Result<Self, MoneyError> {
+        self.0
+            .checked_add(rhs.0)
+            .map(Self)
+            .ok_or(MoneyError::Overflow)
+    }
+
+    /// 残高 `self` から `rhs` を引く。足りなければエラーを返す。
+    pub fn checked_sub(self, rhs: Self) -> Result<Self, MoneyError> {
+        self.0
+            .checked_sub(rhs.0)
+            .map(Self)
+            .ok_or(MoneyError::Insufficient {
+                balance: self,
+                amount: rhs,
+            })
+    }
+
+    pub fn checked_mul(self, factor: u32) -> Result<Self, MoneyError> {
+        self.0
+            .checked_mul(factor)
+            .map(Self)
+            .ok_or(MoneyError::Overflow)
+    }
+}
+
+impl From<u32> for Money {
+    fn from(inner: u32) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl From<Money> for u32 {
+    fn from(money: Money) -> Self {
+        money.get()
+    }
+}
+
+impl std::fmt::Display for Money {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// 所持金が上限を超えたときの振る舞い。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum OverflowPolicy {
+    /// 上限に張り付く。
+    Saturate,
+    /// 0 に戻る (上限 + 1 を法とする)。
+    Wrap,
+    /// ゲームオーバーとなる (エラーを返す)。
+    GameOver,
+}
+
+/// 所持金の上限と、それを超えたときの振る舞い。
+///
+/// 実際のゲームの上限と振る舞いは確認できていない (`MoneyLimit::game()` は `None`)。
+/// それまでは呼び出し側が与える。RAM 上の表現から決まる上限は `RamMap::money_limit()` で得られる。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct MoneyLimit {
+    pub cap: Money,
+    pub policy: OverflowPolicy,
+}
+
+impl Default for MoneyLimit {
+    /// 上限なし (`Money::MAX` を超えたらエラー)。
+    ///
+    /// ゲームの上限ではない。ゲームの上限が確認できたら `MoneyLimit::game()` をデフォルトにする。
+    fn default() -> Self {
+        Self {
+            cap: Money::MAX,
+            policy: OverflowPolicy::GameOver,
+        }
+    }
+}
+
+impl MoneyLimit {
+    pub const fn new(cap: Money, policy: OverflowPolicy) -> Self {
+        Self { cap, policy }
+    }
+
+    /// 実際のゲームの所持金の上限と振る舞い。
+    ///
+    /// 実機で所持金を上限まで増やして確認できていないため、現状は `None` を返す。
+    pub const fn game() -> Option<Self> {
+        None
+    }
+
+    /// 残高 `balance` に `amount` を加える。
+    pub fn credit(self, balance: Money, amount: Money) -> Result<Money, MoneyError> {
+        let sum = u64::from(balance.get()) + u64::from(amount.get());
+        let cap = u64::from(self.cap.get());
+
+        if sum <= cap {
+            return Ok(Money::new(sum as u32));
+        }
+
+        match self.policy {
+            OverflowPolicy::Saturate => Ok(self.cap),
+            OverflowPolicy::Wrap => Ok(Money::new((sum % (cap + 1)) as u32)),
+            OverflowPolicy::GameOver => Err(MoneyError::GameOver),
+        }
+    }
+
+    /// 残高 `balance` で `bet` を賭け、`payout` を受け取った後の残高を返す。
+    pub fn settle(self, balance: Money, bet: Money, payout: Money) -> Result<Money, MoneyError> {
+        let balance = balance.checked_sub(bet)?;
+        self.credit(balance, payout)
+    }
+}
+
+/// 金額の計算エラー。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MoneyError {
+    /// 金額が表現できる範囲を超えた。
+    Overflow,
+    /// 残高が足りない。
+    Insufficient { balance: Money, amount: Money },
+    /// 所持金が上限を超えてゲームオーバーとなった。
+    GameOver,
+}
+
+impl std::fmt::Display for MoneyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Overflow => f.write_str("money overflow"),
+            Self::Insufficient { balance, amount } => {
+                write!(f, "insufficient money: {balance} < {amount}")
+            }
+            Self::GameOver => f.write_str("money exceeds the cap"),
+        }
+    }
+}
+
+impl std::error::Error for MoneyError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_money_limit() {
+        let m = Money::new;
+
+        assert_eq!(m(10).checked_mul(3), Ok(m(30)));
+        assert_eq!(m(u32::MAX).checked_mul(2), Err(MoneyError::Overflow));
+        assert_eq!(
+            m(10).checked_sub(m(11)),
+            Err(MoneyError::Insufficient {
+                balance: m(10),
+                amount: m(11)
+            })
+        );
+
+        let cap = m(999999);
+        let saturate = MoneyLimit::new(cap, OverflowPolicy::Saturate);
+        let wrap = MoneyLimit::new(cap, OverflowPolicy::Wrap);
+        let game_over = MoneyLimit::new(cap, OverflowPolicy::GameOver);
+
+        assert_eq!(saturate.credit(m(999000), m(999)), Ok(cap));
+        assert_eq!(saturate.credit(m(999000), m(5000)), Ok(cap));
+        assert_eq!(wrap.credit(m(999000), m(5000)), Ok(m(4000)));
+        assert_eq!(
+            game_over.credit(m(999000), m(5000)),
+            Err(MoneyError::GameOver)
+        );
+
+        assert_eq!(saturate.settle(m(100), m(30), m(60)), Ok(m(130)));
+        assert!(saturate.settle(m(20), m(30), m(60)).is_err());
+        assert_eq!(
+            MoneyLimit::default().credit(Money::MAX, m(1)),
+            Err(MoneyError::GameOver)
+        );
+    }
+}
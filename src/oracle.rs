@@ -0,0 +1,230 @@
+//! 乱数インデックスごとのゲーム結果のキャッシュ。
+
+use crate::bandit::*;
+use crate::game_version::GameVersion;
+use crate::rng::Rng;
+use crate::rng_deps::{RngDeps, RngSlotSet};
+use crate::trump::{Card, Deck};
+
+/// ポーカーで最初に配られる枚数。
+const POKER_HAND_LEN: usize = 5;
+
+/// 現在の乱数テーブルにおける、各インデックスでのゲーム結果のキャッシュ。
+///
+/// 結果は必要になったときに計算する。テーブルが変化したときは、変化したスロットを読む結果のみ無効化する。
+#[derive(Clone, Debug)]
+pub struct OutcomeOracle {
+    version: GameVersion,
+    rng: Rng,
+    fof: Box<[Option<BanditFofPrize>; 256]>,
+    ms: Box<[Option<BanditMsPrize>; 256]>,
+    ror: Box<[Option<BanditRorPrize>; 256]>,
+    poker: Box<[Option<[Card; POKER_HAND_LEN]>; 256]>,
+    /// インデックスごとの、乱数を 3, 4, 5 個生成するときに読むスロット。
+    reads: Box<[[RngSlotSet; 3]; 256]>,
+}
+
+impl OutcomeOracle {
+    /// 本クレートのテーブルを使うキャッシュを作る。
+    pub fn new(rng: &Rng) -> Self {
+        Self::with_version(&GameVersion::default(), rng)
+    }
+
+    /// バージョン `version` のテーブルを使うキャッシュを作る。
+    pub fn with_version(version: &GameVersion, rng: &Rng) -> Self {
+        let reads =
+            std::array::from_fn(|index| [3, 4, 5].map(|len| RngDeps::of(index as u8, len).reads()));
+
+        Self {
+            version: version.clone(),
+            rng: rng.clone(),
+            fof: Box::new([None; 256]),
+            ms: Box::new([None; 256]),
+            ror: Box::new([None; 256]),
+            poker: Box::new([None; 256]),
+            reads: Box::new(reads),
+        }
+    }
+
+    /// キャッシュが対応している乱数生成器 (インデックスは意味を持たない)。
+    pub fn rng(&self) -> &Rng {
+        &self.rng
+    }
+
+    /// インデックス `index` から乱数を `N` 個生成した結果を返す。状態は変えない。
+    fn randoms<const N: usize>(&mut self, index: u8) -> [u8; N] {
+        self.rng.set_index(index);
+        let mut rs = self.rng.peek_iter();
+        std::array::from_fn(|_| rs.next().unwrap())
+    }
+
+    pub fn bandit_fof_play(&mut self, index: u8) -> BanditFofPrize {
+        let i = usize::from(index);
+        if let Some(prize) = self.fof[i] {
+            return prize;
+        }
+
+        let rs = self.randoms(index);
+        let prize = self.version.bandit_fof_play(rs);
+        self.fof[i] = Some(prize);
+        prize
+    }
+
+    pub fn bandit_ms_play(&mut self, index: u8) -> BanditMsPrize {
+        let i = usize::from(index);
+        if let Some(prize) = self.ms[i] {
+            return prize;
+        }
+
+        let rs = self.randoms(index);
+        let prize = self.version.bandit_ms_play(rs);
+        self.ms[i] = Some(prize);
+        prize
+    }
+
+    pub fn bandit_ror_play(&mut self, index: u8) -> BanditRorPrize {
+        let i = usize::from(index);
+        if let Some(prize) = self.ror[i] {
+            return prize;
+        }
+
+        let rs = self.randoms(index);
+        let prize = self.version.bandit_ror_play(rs);
+        self.ror[i] = Some(prize);
+        prize
+    }
+
+    /// ポーカーで最初に配られる 5 枚を返す。
+    pub fn poker_hand(&mut self, index: u8) -> [Card; POKER_HAND_LEN] {
+        let i = usize::from(index);
+        if let Some(hand) = self.poker[i] {
+            return hand;
+        }
+
+        let rs: [u8; POKER_HAND_LEN] = self.randoms(index);
+        let mut deck = Deck::new();
+        let hand = rs.map(|r| deck.deal(r));
+        self.poker[i] = Some(hand);
+        hand
+    }
+
+    /// 指定したスロットを読む結果を無効化する。
+    pub fn invalidate(&mut self, slots: RngSlotSet) {
+        for (i, [reads_3, reads_4, reads_5]) in self.reads.iter().enumerate() {
+            if !reads_3.is_disjoint(slots) {
+                self.ms[i] = None;
+                self.ror[i] = None;
+            }
+            if !reads_4.is_disjoint(slots) {
+                self.fof[i] = None;
+            }
+            if !reads_5.is_disjoint(slots) {
+                self.poker[i] = None;
+            }
+        }
+    }
+
+    /// インデックス `index` から乱数を `len` 個消費する手を反映する。
+    pub fn advance(&mut self, index: u8, len: usize) {
+        self.rng.set_index(index);
+        for _ in 0..len {
+            self.rng.gen();
+        }
+        self.invalidate(RngDeps::of(index, len).writes());
+    }
+
+    /// 乱数テーブルの変化を反映する。変化したスロットを読む結果のみ無効化する。
+    pub fn update(&mut self, rng: &Rng) {
+        let changed: RngSlotSet = self
+            .rng
+            .table()
+            .iter()
+            .zip(rng.table())
+            .enumerate()
+            .filter(|(_, (lhs, rhs))| lhs != rhs)
+            .map(|(slot, _)| slot)
+            .collect();
+
+        if !changed.is_empty() {
+            self.invalidate(changed);
+        }
+        self.rng = rng.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_oracle_matches_direct() {
+        let mut rng = Rng::new();
+        let mut oracle = OutcomeOracle::new(&rng);
+
+        // 手の選択にも乱数生成器を使う。
+        let mut rng_choice = Rng::with_index(77);
+
+        for step in 0..40 {
+            for index in 0..=0xFF {
+                let mut rng_play = rng.clone();
+                rng_play.set_index(index);
+                let rs: [u8; 5] = rng_play.gen_array();
+
+                assert_eq!(
+                    oracle.bandit_fof_play(index),
+                    bandit_fof_play([rs[0], rs[1], rs[2], rs[3]])
+                );
+                assert_eq!(
+                    oracle.bandit_ms_play(index),
+                    bandit_ms_play([rs[0], rs[1], rs[2]])
+                );
+                assert_eq!(
+                    oracle.bandit_ror_play(index),
+                    bandit_ror_play([rs[0], rs[1], rs[2]])
+                );
+
+                let mut deck = Deck::new();
+                assert_eq!(oracle.poker_hand(index), rs.map(|r| deck.deal(r)));
+            }
+
+            let [index, len] = rng_choice.gen_array();
+            let len = usize::from(len % 10) + 1;
+            rng.set_index(index);
+            rng.fill(&mut vec![0; len]);
+            if step % 2 == 0 {
+                oracle.update(&rng);
+            } else {
+                oracle.advance(index, len);
+            }
+            assert_eq!(oracle.rng().table(), rng.table());
+        }
+    }
+
+    #[test]
+    fn test_invalidate_partially() {
+        let mut oracle = OutcomeOracle::new(&Rng::new());
+        for index in 0..=0xFF {
+            oracle.bandit_fof_play(index);
+        }
+
+        // 3 スロットしか書き換わらないので、ほとんどの結果は残る。
+        oracle.advance(10, 3);
+        let cached_count = oracle.fof.iter().filter(|prize| prize.is_some()).count();
+        assert!(cached_count > 230, "{cached_count}");
+    }
+
+    #[test]
+    fn test_with_version() {
+        let mut version = GameVersion::default();
+        version.pay_tables.ror = [1; 8];
+
+        let rng = Rng::new();
+        let mut oracle = OutcomeOracle::with_version(&version, &rng);
+        for index in 0..=0xFF {
+            let mut rng_play = rng.clone();
+            rng_play.set_index(index);
+            let rs = rng_play.gen_array();
+            assert_eq!(oracle.bandit_ror_play(index), version.bandit_ror_play(rs));
+        }
+    }
+}
@@ -0,0 +1,529 @@
+//! ゲームの状態の RAM 上の配置。
+//!
+//! 正式な配置は `RamMap::game()` が返す。テキスト形式はこれを上書きしたい場合にのみ使う。
+//! テキスト形式は以下の通り (`#` 以降はコメント。アドレスは例であり、実際のものではない):
+//!
+//! ```text
+//! rng_index     = 0x0012
+//! rng_table     = 0x0300
+//! money         = 0x0020:3:bcd-le
+//! jackpot_small = 0x0024:3:bcd-le
+//! jackpot_big   = 0x0028:3:bcd-le
+//! location      = 0x0030
+//! # 上記以外のキーは各ゲーム固有の値として扱う。
+//! poker.bet     = 0x0040:1:bin-le
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::money::{Money, MoneyLimit, OverflowPolicy};
+use crate::rng::{Rng, RngRamLayout};
+
+/// NES の内部 RAM (2 KiB)。
+pub type WorkRam = [u8; 0x800];
+
+/// ゲームの状態の RAM 上の配置。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RamMap {
+    pub rng: RngRamLayout,
+    pub money: RamNumber,
+    pub jackpot_small: RamNumber,
+    pub jackpot_big: RamNumber,
+    pub location: u16,
+    /// 各ゲーム固有の値 ("poker.bet" など)。
+    pub fields: BTreeMap<String, RamNumber>,
+}
+
+impl RamMap {
+    /// ゲーム本体の RAM 配置 (所持金の表現を含む)。
+    ///
+    /// 実機の RAM ダンプとの照合が済んでいないため、現状は `None` を返す。
+    /// 確認でき次第ここにアドレスと所持金の表現を記載する。それまではテキスト形式で与えること。
+    pub fn game() -> Option<Self> {
+        None
+    }
+
+    pub fn rng(&self, ram: &WorkRam) -> Rng {
+        Rng::from_ram(ram, self.rng)
+    }
+
+    pub fn set_rng(&self, ram: &mut WorkRam, rng: &Rng) {
+        rng.write_ram(ram, self.rng);
+    }
+
+    pub fn money(&self, ram: &WorkRam) -> Money {
+        Money::new(self.money.read(ram))
+    }
+
+    pub fn set_money(&self, ram: &mut WorkRam, value: Money) -> Result<(), RamError> {
+        self.money.write(ram, value.get())
+    }
+
+    /// 所持金の RAM 上の表現で表せる最大値を上限とする。
+    pub fn money_limit(&self, policy: OverflowPolicy) -> MoneyLimit {
+        MoneyLimit::new(Money::new(self.money.max_value()), policy)
+    }
+
+    pub fn jackpot_small(&self, ram: &WorkRam) -> Money {
+        Money::new(self.jackpot_small.read(ram))
+    }
+
+    pub fn set_jackpot_small(&self, ram: &mut WorkRam, value: Money) -> Result<(), RamError> {
+        self.jackpot_small.write(ram, value.get())
+    }
+
+    pub fn jackpot_big(&self, ram: &WorkRam) -> Money {
+        Money::new(self.jackpot_big.read(ram))
+    }
+
+    pub fn set_jackpot_big(&self, ram: &mut WorkRam, value: Money) -> Result<(), RamError> {
+        self.jackpot_big.write(ram, value.get())
+    }
+
+    pub fn location(&self, ram: &WorkRam) -> u8 {
+        ram[usize::from(self.location)]
+    }
+
+    pub fn set_location(&self, ram: &mut WorkRam, value: u8) {
+        ram[usize::from(self.location)] = value;
+    }
+
+    /// 各ゲーム固有の値を読み取る。
+    pub fn field(&self, name: &str, ram: &WorkRam) -> Result<u32, RamError> {
+        let number = self
+            .fields
+            .get(name)
+            .ok_or_else(|| RamError::UnknownField(name.to_owned()))?;
+        Ok(number.read(ram))
+    }
+
+    /// 各ゲーム固有の値を書き込む。
+    pub fn set_field(&self, name: &str, ram: &mut WorkRam, value: u32) -> Result<(), RamError> {
+        let number = self
+            .fields
+            .get(name)
+            .ok_or_else(|| RamError::UnknownField(name.to_owned()))?;
+        number.write(ram, value)
+    }
+}
+
+impl std::str::FromStr for RamMap {
+    type Err = ParseRamMapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut entries = BTreeMap::<String, (usize, String)>::new();
+
+        for (i, line) in s.lines().enumerate() {
+            let lineno = i + 1;
+            let err = |message: String| ParseRamMapError { lineno, message };
+
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| err("expected KEY = VALUE".to_owned()))?;
+            let (key, value) = (key.trim(), value.trim());
+
+            if entries
+                .insert(key.to_owned(), (lineno, value.to_owned()))
+                .is_some()
+            {
+                return Err(err(format!("duplicate key: {key}")));
+            }
+        }
+
+        let mut take = |key: &str| {
+            entries.remove(key).ok_or_else(|| ParseRamMapError {
+                lineno: 0,
+                message: format!("missing key: {key}"),
+            })
+        };
+        let addr = |(lineno, value): (usize, String)| {
+            parse_addr(&value).map_err(|message| ParseRamMapError { lineno, message })
+        };
+        let number = |(lineno, value): (usize, String)| {
+            value
+                .parse::<RamNumber>()
+                .map_err(|message| ParseRamMapError { lineno, message })
+        };
+
+        let rng_index = take("rng_index")?;
+        let lineno = rng_index.0;
+        let rng = RngRamLayout::new(addr(rng_index)?, addr(take("rng_table")?)?).ok_or(
+            ParseRamMapError {
+                lineno,
+                message: "invalid RNG layout".to_owned(),
+            },
+        )?;
+        let money = number(take("money")?)?;
+        let jackpot_small = number(take("jackpot_small")?)?;
+        let jackpot_big = number(take("jackpot_big")?)?;
+        let location = addr(take("location")?)?;
+
+        let fields = entries
+            .into_iter()
+            .map(|(key, entry)| Ok((key, number(entry)?)))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            rng,
+            money,
+            jackpot_small,
+            jackpot_big,
+            location,
+            fields,
+        })
+    }
+}
+
+/// RAM 上の数値。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct RamNumber {
+    addr: u16,
+    size: u8,
+    format: NumberFormat,
+}
+
+impl RamNumber {
+    /// 配置が RAM に収まらない、または値が `u32` に収まらない場合は `None` を返す。
+    pub const fn new(addr: u16, size: u8, format: NumberFormat) -> Option<Self> {
+        if size == 0 || size > 4 {
+            return None;
+        }
+        if addr as usize + size as usize > std::mem::size_of::<WorkRam>() {
+            return None;
+        }
+
+        Some(Self { addr, size, format })
+    }
+
+    pub const fn addr(self) -> u16 {
+        self.addr
+    }
+
+    /// バイト数。
+    pub const fn size(self) -> u8 {
+        self.size
+    }
+
+    pub const fn format(self) -> NumberFormat {
+        self.format
+    }
+
+    /// 表現可能な最大値を返す。
+    pub const fn max_value(self) -> u32 {
+        let base = self.format.base() as u64;
+        let mut max = 1;
+        let mut i = 0;
+        while i < self.size {
+            max *= base;
+            i += 1;
+        }
+
+        (max - 1) as u32
+    }
+
+    /// RAM から値を読み取る。BCD の各桁が 0..=9 であるかどうかは検査しない。
+    pub fn read(self, ram: &WorkRam) -> u32 {
+        let base = self.format.base();
+        let digit = |b: u8| -> u32 {
+            if self.format.is_bcd() {
+                u32::from(b >> 4) * 10 + u32::from(b & 0xF)
+            } else {
+                u32::from(b)
+            }
+        };
+
+        let fold = |acc: u32, &b: &u8| acc * base + digit(b);
+        let bytes = self.bytes(ram);
+        if self.format.is_big_endian() {
+            bytes.iter().fold(0, fold)
+        } else {
+            bytes.iter().rev().fold(0, fold)
+        }
+    }
+
+    /// RAM に値を書き込む。値が表現可能な範囲外ならエラーを返し、RAM は変更しない。
+    pub fn write(self, ram: &mut WorkRam, value: u32) -> Result<(), RamError> {
+        let max = self.max_value();
+        if value > max {
+            return Err(RamError::ValueOutOfRange { value, max });
+        }
+
+        let base = self.format.base();
+        let is_bcd = self.format.is_bcd();
+        let is_big_endian = self.format.is_big_endian();
+
+        let bytes = self.bytes_mut(ram);
+        let mut value = value;
+        for i in 0..bytes.len() {
+            let digit = (value % base) as u8;
+            value /= base;
+
+            let b = if is_bcd {
+                ((digit / 10) << 4) | (digit % 10)
+            } else {
+                digit
+            };
+            let pos = if is_big_endian {
+                bytes.len() - 1 - i
+            } else {
+                i
+            };
+            bytes[pos] = b;
+        }
+
+        Ok(())
+    }
+
+    fn bytes(self, ram: &WorkRam) -> &[u8] {
+        &ram[usize::from(self.addr)..][..usize::from(self.size)]
+    }
+
+    fn bytes_mut(self, ram: &mut WorkRam) -> &mut [u8] {
+        &mut ram[usize::from(self.addr)..][..usize::from(self.size)]
+    }
+}
+
+impl std::str::FromStr for RamNumber {
+    type Err = String;
+
+    /// "ADDR:SIZE:FORMAT" 形式 ("0x0020:3:bcd-le" など) をパースする。
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<_> = s.split(':').map(str::trim).collect();
+        let [addr, size, format] = fields[..] else {
+            return Err(format!("expected ADDR:SIZE:FORMAT: {s:?}"));
+        };
+
+        let addr = parse_addr(addr)?;
+        let size = size
+            .parse::<u8>()
+            .map_err(|_| format!("invalid size: {size:?}"))?;
+        let format = format.parse::<NumberFormat>()?;
+
+        Self::new(addr, size, format).ok_or_else(|| format!("invalid RAM number: {s:?}"))
+    }
+}
+
+impl std::fmt::Display for RamNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "0x{:04X}:{}:{}", self.addr, self.size, self.format)
+    }
+}
+
+/// RAM 上の数値の形式。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum NumberFormat {
+    /// 2 進数、リトルエンディアン。
+    BinaryLe,
+    /// 2 進数、ビッグエンディアン。
+    BinaryBe,
+    /// 1 byte に 2 桁の BCD、下位桁が先。
+    BcdLe,
+    /// 1 byte に 2 桁の BCD、上位桁が先。
+    BcdBe,
+}
+
+impl NumberFormat {
+    const fn base(self) -> u32 {
+        if self.is_bcd() {
+            100
+        } else {
+            0x100
+        }
+    }
+
+    const fn is_bcd(self) -> bool {
+        matches!(self, Self::BcdLe | Self::BcdBe)
+    }
+
+    const fn is_big_endian(self) -> bool {
+        matches!(self, Self::BinaryBe | Self::BcdBe)
+    }
+}
+
+impl std::str::FromStr for NumberFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bin-le" => Ok(Self::BinaryLe),
+            "bin-be" => Ok(Self::BinaryBe),
+            "bcd-le" => Ok(Self::BcdLe),
+            "bcd-be" => Ok(Self::BcdBe),
+            _ => Err(format!("unknown number format: {s:?}")),
+        }
+    }
+}
+
+impl std::fmt::Display for NumberFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::BinaryLe => "bin-le",
+            Self::BinaryBe => "bin-be",
+            Self::BcdLe => "bcd-le",
+            Self::BcdBe => "bcd-be",
+        };
+        f.write_str(s)
+    }
+}
+
+/// RAM の読み書きのエラー。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RamError {
+    UnknownField(String),
+    ValueOutOfRange { value: u32, max: u32 },
+}
+
+impl std::fmt::Display for RamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownField(name) => write!(f, "unknown RAM field: {name}"),
+            Self::ValueOutOfRange { value, max } => {
+                write!(f, "value {value} exceeds the maximum {max}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RamError {}
+
+/// `RamMap` のパースエラー。`lineno` は 1-based (0 はファイル全体に関するエラー)。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseRamMapError {
+    pub lineno: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseRamMapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.lineno == 0 {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "line {}: {}", self.lineno, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ParseRamMapError {}
+
+fn parse_addr(s: &str) -> Result<u16, String> {
+    let addr = if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix('$')) {
+        u16::from_str_radix(hex, 16)
+    } else {
+        s.parse::<u16>()
+    };
+
+    addr.ok()
+        .filter(|&addr| usize::from(addr) < std::mem::size_of::<WorkRam>())
+        .ok_or_else(|| format!("invalid RAM address: {s:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = "
+        # テスト用の配置。
+        rng_index     = 0x0012
+        rng_table     = $0300
+        money         = 0x0020:3:bcd-le
+        jackpot_small = 0x0024:3:bcd-be
+        jackpot_big   = 0x0028:4:bin-le  # 末尾のコメント
+        location      = 48
+        poker.bet     = 0x0040:1:bin-le
+    ";
+
+    #[test]
+    fn test_ram_map_parse() {
+        let map: RamMap = MAP.parse().unwrap();
+
+        assert_eq!(map.rng, RngRamLayout::new(0x12, 0x300).unwrap());
+        assert_eq!(map.money.to_string(), "0x0020:3:bcd-le");
+        assert_eq!(map.jackpot_big.to_string(), "0x0028:4:bin-le");
+        assert_eq!(map.location, 0x30);
+        assert_eq!(map.fields.len(), 1);
+
+        assert_eq!(
+            "rng_index = 0x12".parse::<RamMap>().unwrap_err().message,
+            "missing key: rng_table"
+        );
+        assert_eq!(
+            MAP.replace("48", "0x800")
+                .parse::<RamMap>()
+                .unwrap_err()
+                .lineno,
+            8
+        );
+    }
+
+    #[test]
+    fn test_ram_map_access() {
+        let map: RamMap = MAP.parse().unwrap();
+        let mut ram = [0; 0x800];
+
+        map.set_money(&mut ram, Money::new(123456)).unwrap();
+        assert_eq!(ram[0x20..0x23], [0x56, 0x34, 0x12]);
+        map.set_jackpot_small(&mut ram, Money::new(750)).unwrap();
+        assert_eq!(ram[0x24..0x27], [0x00, 0x07, 0x50]);
+        map.set_jackpot_big(&mut ram, Money::new(100000)).unwrap();
+        map.set_location(&mut ram, 3);
+        map.set_field("poker.bet", &mut ram, 2).unwrap();
+
+        assert_eq!(map.money(&ram), Money::new(123456));
+        assert_eq!(map.jackpot_small(&ram), Money::new(750));
+        assert_eq!(map.jackpot_big(&ram), Money::new(100000));
+        assert_eq!(map.location(&ram), 3);
+        assert_eq!(map.field("poker.bet", &ram), Ok(2));
+
+        assert_eq!(
+            map.set_money(&mut ram, Money::new(1000000)),
+            Err(RamError::ValueOutOfRange {
+                value: 1000000,
+                max: 999999
+            })
+        );
+        assert_eq!(map.money(&ram), Money::new(123456));
+        assert!(map.field("blackjack.bet", &ram).is_err());
+
+        let rng = Rng::with_index(42);
+        map.set_rng(&mut ram, &rng);
+        assert_eq!(map.rng(&ram), rng);
+    }
+
+    #[test]
+    fn test_ram_number_roundtrip() {
+        let formats = [
+            NumberFormat::BinaryLe,
+            NumberFormat::BinaryBe,
+            NumberFormat::BcdLe,
+            NumberFormat::BcdBe,
+        ];
+
+        for format in formats {
+            for size in 1..=4 {
+                let number = RamNumber::new(0x10, size, format).unwrap();
+                for value in [0, 1, 99, 255, 9999, 65535, number.max_value()] {
+                    let mut ram = [0; 0x800];
+                    match number.write(&mut ram, value) {
+                        Ok(()) => assert_eq!(number.read(&ram), value),
+                        Err(_) => assert!(value > number.max_value()),
+                    }
+                }
+            }
+        }
+
+        assert_eq!(
+            RamNumber::new(0, 4, NumberFormat::BinaryLe)
+                .unwrap()
+                .max_value(),
+            u32::MAX
+        );
+        assert!(RamNumber::new(0x7FE, 3, NumberFormat::BinaryLe).is_none());
+        assert!(RamNumber::new(0x000, 0, NumberFormat::BinaryLe).is_none());
+        assert!(RamNumber::new(0x000, 5, NumberFormat::BcdLe).is_none());
+    }
+}
@@ -1,10 +1,133 @@
-const TABLE_LEN: usize = 250;
+use arrayvec::ArrayVec;
+
+use crate::ram::WorkRam;
+
+pub(crate) const TABLE_LEN: usize = 250;
 
 type RngTable = [u8; TABLE_LEN];
 
+/// 乱数インデックス。
+///
+/// 通常は `0..250` の値をとり、1 回乱数を生成するごとに 1 ずつ進む (249 の次は 0)。
+///
+/// `250..=255` はテーブルの範囲外で、ホールの外でのみ到達可能。
+/// この場合、インデックス `250 + k` は通常のインデックス `k` と同じスロットを読み書きし、次は 0 となる。
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct RngIndex(u8);
+
+impl RngIndex {
+    pub const fn new(inner: u8) -> Self {
+        Self(inner)
+    }
+
+    pub const fn get(self) -> u8 {
+        self.0
+    }
+
+    /// テーブルの範囲外 (`250..=255`) かどうかを返す。
+    pub const fn is_out_of_range(self) -> bool {
+        self.0 >= TABLE_LEN as u8
+    }
+
+    /// 値を読むだけのスロット。
+    pub const fn slot_y(self) -> usize {
+        (if self.is_out_of_range() {
+            self.0 - TABLE_LEN as u8
+        } else {
+            self.0
+        }) as usize
+    }
+
+    /// 値を読み、生成した乱数で上書きするスロット。
+    pub const fn slot_x(self) -> usize {
+        (if self.0 >= 147 {
+            self.0 - 147
+        } else {
+            self.0 + 103
+        }) as usize
+    }
+
+    pub const fn next(self) -> Self {
+        let nxt = self.0.wrapping_add(1);
+
+        Self(if nxt >= TABLE_LEN as u8 { 0 } else { nxt })
+    }
+
+    /// 全てのインデックス (`0..=255`) を列挙する。
+    pub fn all() -> impl DoubleEndedIterator<Item = Self> + Clone {
+        (0..=0xFF).map(Self)
+    }
+
+    /// テーブルの範囲外のインデックス (`250..=255`) を列挙する。
+    pub fn all_out_of_range() -> impl DoubleEndedIterator<Item = Self> + Clone {
+        (TABLE_LEN as u8..=0xFF).map(Self)
+    }
+}
+
+impl std::fmt::Display for RngIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::fmt::UpperHex for RngIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<u8> for RngIndex {
+    fn from(inner: u8) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl From<RngIndex> for u8 {
+    fn from(index: RngIndex) -> Self {
+        index.get()
+    }
+}
+
+/// RAM 上の乱数生成器の配置 (インデックスとテーブルのアドレス)。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct RngRamLayout {
+    index_addr: u16,
+    table_addr: u16,
+}
+
+impl RngRamLayout {
+    /// 配置が RAM に収まらないか、インデックスがテーブルと重なる場合は `None` を返す。
+    pub const fn new(index_addr: u16, table_addr: u16) -> Option<Self> {
+        const RAM_LEN: usize = std::mem::size_of::<WorkRam>();
+
+        let index = index_addr as usize;
+        let table = table_addr as usize;
+
+        if index >= RAM_LEN || table + TABLE_LEN > RAM_LEN {
+            return None;
+        }
+        if table <= index && index < table + TABLE_LEN {
+            return None;
+        }
+
+        Some(Self {
+            index_addr,
+            table_addr,
+        })
+    }
+
+    pub const fn index_addr(self) -> u16 {
+        self.index_addr
+    }
+
+    pub const fn table_addr(self) -> u16 {
+        self.table_addr
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Rng {
-    index: u8,
+    index: RngIndex,
     table: RngTable,
 }
 
@@ -16,7 +139,7 @@ impl Default for Rng {
 
 impl Rng {
     #[rustfmt::skip]
-    const TABLE_INI: RngTable = [
+    pub(crate) const TABLE_INI: RngTable = [
         0xC9, 0xA1, 0xCA, 0x63, 0xF1, 0x36, 0xDB, 0xC8, 0x56, 0xD5, 0xCE, 0xB6, 0xA7, 0x18, 0xF1, 0x1C,
         0xC7, 0x88, 0x4C, 0x40, 0x5A, 0x98, 0x49, 0xEF, 0x28, 0xC1, 0x29, 0x74, 0x7E, 0x35, 0x49, 0x3A,
         0x97, 0xD4, 0xA1, 0xE8, 0xDA, 0xD8, 0x9C, 0x6E, 0x1A, 0xD0, 0x18, 0xDB, 0x21, 0xE1, 0x71, 0x19,
@@ -39,81 +162,284 @@ impl Rng {
         Self::default()
     }
 
-    pub fn with_index(index: u8) -> Self {
+    /// 初期テーブルと指定したインデックスで乱数生成器を作る。
+    ///
+    /// インデックスは `250..=255` でもよい (`RngIndex` を参照)。
+    pub fn with_index(index: impl Into<RngIndex>) -> Self {
         Self {
-            index,
+            index: index.into(),
             table: Self::TABLE_INI,
         }
     }
 
-    pub fn index(&self) -> u8 {
+    /// インデックスとテーブルを直接指定して乱数生成器を作る。
+    pub fn from_raw(index: impl Into<RngIndex>, table: &RngTable) -> Self {
+        Self {
+            index: index.into(),
+            table: *table,
+        }
+    }
+
+    /// RAM ダンプから乱数生成器の状態を読み込む。
+    pub fn from_ram(ram: &WorkRam, layout: RngRamLayout) -> Self {
+        let index = RngIndex::new(ram[usize::from(layout.index_addr)]);
+        let table_addr = usize::from(layout.table_addr);
+        let table = ram[table_addr..][..TABLE_LEN].try_into().unwrap();
+
+        Self { index, table }
+    }
+
+    /// 乱数生成器の状態を RAM に書き込む。
+    pub fn write_ram(&self, ram: &mut WorkRam, layout: RngRamLayout) {
+        ram[usize::from(layout.index_addr)] = self.index.get();
+        let table_addr = usize::from(layout.table_addr);
+        ram[table_addr..][..TABLE_LEN].copy_from_slice(&self.table);
+    }
+
+    pub fn index(&self) -> RngIndex {
         self.index
     }
 
-    pub fn set_index(&mut self, index: u8) {
-        self.index = index;
+    pub fn set_index(&mut self, index: impl Into<RngIndex>) {
+        self.index = index.into();
     }
 
     pub fn table(&self) -> &RngTable {
         &self.table
     }
 
+    /// 現在のテーブルにおいて、先頭 `len` 個の出力が他のどのインデックスとも異なる
+    /// テーブル範囲外のインデックスを列挙する。
+    ///
+    /// 範囲外のインデックスどうしで出力が一致する場合、小さい方のみを返す。
+    pub fn distinct_out_of_range_indices(&self, len: usize) -> Vec<RngIndex> {
+        let mut rng = self.clone();
+        let mut outputs = |index: RngIndex| -> Vec<u8> {
+            rng.set_index(index);
+            let rs = rng.gen_iter().take(len).collect();
+            rng.undo(index, len);
+            rs
+        };
+
+        let mut seen: Vec<Vec<u8>> = RngIndex::all()
+            .filter(|index| !index.is_out_of_range())
+            .map(&mut outputs)
+            .collect();
+
+        RngIndex::all_out_of_range()
+            .filter(|&index| {
+                let rs = outputs(index);
+                if seen.contains(&rs) {
+                    false
+                } else {
+                    seen.push(rs);
+                    true
+                }
+            })
+            .collect()
+    }
+
+    /// 状態を変えずに、今後生成される乱数列を返す。
+    ///
+    /// テーブル全体は複製せず、上書きされたスロットのみを保持する。
+    pub fn peek_iter(&self) -> RngPeek<'_> {
+        RngPeek {
+            table: &self.table,
+            index: self.index,
+            first: None,
+            ring: ArrayVec::new(),
+            count: 0,
+            slot_last: 0,
+        }
+    }
+
+    /// 状態を変えずに、`n` 回後 (0 なら次) に生成される乱数を返す。
+    pub fn peek(&self, n: usize) -> u8 {
+        self.peek_iter().nth(n).unwrap()
+    }
+
     pub fn gen(&mut self) -> u8 {
         let r = self.gen_helper(self.index);
 
-        self.index = Self::index_next(self.index);
+        self.index = self.index.next();
 
         r
     }
 
-    pub fn undo(&mut self, index_start: u8, len: usize) {
+    /// `N` 個の乱数を生成する。
+    pub fn gen_array<const N: usize>(&mut self) -> [u8; N] {
+        let mut rs = [0; N];
+        self.fill(&mut rs);
+        rs
+    }
+
+    /// `buf` を生成した乱数で埋める。
+    ///
+    /// インデックスを `0..103`, `103..147`, `147..250` の区間に分けると、各区間内で読むスロットと
+    /// 書くスロットは重ならないので、区間ごとにまとめて生成する。
+    pub fn fill(&mut self, buf: &mut [u8]) {
+        let mut buf = buf;
+
+        // 範囲外のインデックスは最初の 1 回のみ現れる。
+        if self.index.is_out_of_range() {
+            let Some((head, rest)) = buf.split_first_mut() else {
+                return;
+            };
+            *head = self.gen();
+            buf = rest;
+        }
+
+        while !buf.is_empty() {
+            let index = self.index;
+            let start = usize::from(index.get());
+            let end = match start {
+                0..=102 => 103,
+                103..=146 => 147,
+                _ => TABLE_LEN,
+            };
+            let n = (end - start).min(buf.len());
+
+            let (head, rest) = std::mem::take(&mut buf).split_at_mut(n);
+            Self::fill_segment(&mut self.table, index.slot_y(), index.slot_x(), head);
+            buf = rest;
+
+            self.index = RngIndex::new(if start + n == TABLE_LEN {
+                0
+            } else {
+                (start + n) as u8
+            });
+        }
+    }
+
+    /// 読むスロット `y..y+n` と書くスロット `x..x+n` が重ならない区間をまとめて生成する。
+    fn fill_segment(table: &mut RngTable, y: usize, x: usize, out: &mut [u8]) {
+        let n = out.len();
+        let (ys, xs) = if x < y {
+            let (lo, hi) = table.split_at_mut(y);
+            (&hi[..n], &mut lo[x..][..n])
+        } else {
+            let (lo, hi) = table.split_at_mut(x);
+            (&lo[y..][..n], &mut hi[..n])
+        };
+
+        for ((r, t_x), t_y) in out.iter_mut().zip(xs).zip(ys) {
+            *t_x ^= t_y;
+            *r = *t_x;
+        }
+    }
+
+    /// 乱数を生成し続けるイテレータを返す。
+    pub fn gen_iter(&mut self) -> RngIter<'_> {
+        RngIter { rng: self }
+    }
+
+    pub fn undo(&mut self, index_start: impl Into<RngIndex>, len: usize) {
+        let index_start = index_start.into();
         let mut index = index_start;
 
         for _ in 0..len {
             self.gen_helper(index);
-            index = Self::index_next(index);
+            index = index.next();
         }
 
         self.index = index_start;
     }
 
-    fn gen_helper(&mut self, index: u8) -> u8 {
-        let y = Self::f_y(index);
-        let x = Self::f_x(index);
+    fn gen_helper(&mut self, index: RngIndex) -> u8 {
+        let y = index.slot_y();
+        let x = index.slot_x();
 
         let r = self.table[y] ^ self.table[x];
         self.table[x] = r;
 
         r
     }
+}
 
-    fn f_y(index: u8) -> usize {
-        usize::from(if index >= TABLE_LEN as u8 {
-            index - TABLE_LEN as u8
-        } else {
-            index
-        })
+/// `Rng::gen_iter()` の返すイテレータ。終わりはない。
+#[derive(Debug)]
+pub struct RngIter<'a> {
+    rng: &'a mut Rng,
+}
+
+impl Iterator for RngIter<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        Some(self.rng.gen())
     }
 
-    fn f_x(index: u8) -> usize {
-        usize::from(if index >= 147 {
-            index - 147
-        } else {
-            index + 103
-        })
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
     }
+}
 
-    fn index_next(index: u8) -> u8 {
-        let nxt = index.wrapping_add(1);
+impl std::iter::FusedIterator for RngIter<'_> {}
 
-        if nxt >= TABLE_LEN as u8 {
-            0
+/// `Rng::peek_iter()` の返すイテレータ。終わりはない。
+///
+/// 通常のインデックスで上書きされるスロットは連続しているので (`slot_x` は 1 ずつ進む)、
+/// 直近に上書きした値をリングバッファに持てばどのスロットが上書き済みかも分かる。
+#[derive(Clone, Debug)]
+pub struct RngPeek<'a> {
+    table: &'a RngTable,
+    index: RngIndex,
+    /// 範囲外のインデックスから始めた場合の、最初の上書き (スロット, 値)。
+    first: Option<(usize, u8)>,
+    /// 通常のインデックスでの k 回目の上書きの値を `k % TABLE_LEN` 番目に持つ。
+    ring: ArrayVec<u8, TABLE_LEN>,
+    /// 通常のインデックスでの上書き回数。
+    count: usize,
+    /// 最後に上書きしたスロット。
+    slot_last: usize,
+}
+
+impl RngPeek<'_> {
+    fn get(&self, slot: usize) -> u8 {
+        if self.count > 0 {
+            let dist = (self.slot_last + TABLE_LEN - slot) % TABLE_LEN;
+            if dist < self.count {
+                return self.ring[(self.count - 1 - dist) % TABLE_LEN];
+            }
+        }
+
+        match self.first {
+            Some((slot_first, r)) if slot_first == slot => r,
+            _ => self.table[slot],
+        }
+    }
+}
+
+impl Iterator for RngPeek<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        let x = self.index.slot_x();
+        let r = self.get(self.index.slot_y()) ^ self.get(x);
+
+        if self.index.is_out_of_range() {
+            self.first = Some((x, r));
         } else {
-            nxt
+            if self.ring.is_full() {
+                self.ring[self.count % TABLE_LEN] = r;
+            } else {
+                self.ring.push(r);
+            }
+            self.count += 1;
+            self.slot_last = x;
         }
+        self.index = self.index.next();
+
+        Some(r)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
     }
 }
 
+impl std::iter::FusedIterator for RngPeek<'_> {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -134,4 +460,109 @@ mod tests {
             assert_eq!(rng, rng_orig);
         }
     }
+
+    #[test]
+    fn test_peek() {
+        let mut rng_base = Rng::new();
+        for _ in 0..777 {
+            rng_base.gen();
+        }
+
+        for index_start in 0..=0xFF {
+            let mut rng = rng_base.clone();
+            rng.set_index(index_start);
+            let rng_orig = rng.clone();
+
+            let peeked: Vec<u8> = rng.peek_iter().take(700).collect();
+            assert_eq!(rng.peek(10), peeked[10]);
+            assert_eq!(rng, rng_orig);
+
+            let actual: Vec<u8> = (0..700).map(|_| rng.gen()).collect();
+            assert_eq!(peeked, actual);
+        }
+    }
+
+    #[test]
+    fn test_fill() {
+        for index_start in 0..=0xFF {
+            for len in [0, 1, 2, 50, 103, 147, 250, 251, 1000] {
+                let mut rng_naive = Rng::with_index(index_start);
+                let expected: Vec<u8> = (0..len).map(|_| rng_naive.gen()).collect();
+
+                let mut rng = Rng::with_index(index_start);
+                let mut buf = vec![0; len];
+                rng.fill(&mut buf);
+                assert_eq!(buf, expected);
+                assert_eq!(rng, rng_naive);
+            }
+        }
+
+        let mut rng = Rng::with_index(200);
+        let mut rng_naive = rng.clone();
+        let rs: [u8; 5] = rng.gen_array();
+        let expected: Vec<u8> = rng_naive.gen_iter().take(5).collect();
+        assert_eq!(rs[..], expected);
+        assert_eq!(rng, rng_naive);
+    }
+
+    #[test]
+    fn test_index_out_of_range_slots() {
+        for k in 0..6 {
+            let index = RngIndex::new(250 + k);
+            assert!(index.is_out_of_range());
+            assert_eq!(index.slot_y(), usize::from(k));
+            assert_eq!(index.slot_x(), 103 + usize::from(k));
+            assert_eq!(index.next(), RngIndex::new(0));
+
+            // 最初の出力は通常のインデックス k と一致する。
+            let normal = RngIndex::new(k);
+            assert_eq!(index.slot_y(), normal.slot_y());
+            assert_eq!(index.slot_x(), normal.slot_x());
+        }
+
+        assert!(!RngIndex::new(249).is_out_of_range());
+        assert_eq!(RngIndex::new(249).next(), RngIndex::new(0));
+        assert_eq!(RngIndex::all_out_of_range().count(), 6);
+    }
+
+    #[test]
+    fn test_distinct_out_of_range_indices() {
+        let rng = Rng::new();
+
+        // 1 個だけなら通常のインデックスと区別できない。
+        assert!(rng.distinct_out_of_range_indices(1).is_empty());
+
+        // 2 個目以降は次のインデックスが 0 になるので区別できるようになる。
+        assert_eq!(
+            rng.distinct_out_of_range_indices(3),
+            RngIndex::all_out_of_range().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_ram_layout() {
+        assert!(RngRamLayout::new(0x7FF, 0x700).is_some());
+        assert!(RngRamLayout::new(0x800, 0x000).is_none());
+        assert!(RngRamLayout::new(0x000, 0x707).is_none());
+        assert!(RngRamLayout::new(0x100, 0x100).is_none());
+        assert!(RngRamLayout::new(0x1F9, 0x100).is_none());
+        assert!(RngRamLayout::new(0x1FA, 0x100).is_some());
+    }
+
+    #[test]
+    fn test_from_ram() {
+        let layout = RngRamLayout::new(0x0012, 0x0300).unwrap();
+
+        let mut rng = Rng::with_index(123);
+        for _ in 0..1000 {
+            rng.gen();
+        }
+
+        let mut ram = [0; 0x800];
+        rng.write_ram(&mut ram, layout);
+        assert_eq!(ram[0x12], rng.index().get());
+
+        assert_eq!(Rng::from_ram(&ram, layout), rng);
+        assert_eq!(Rng::from_raw(rng.index(), rng.table()), rng);
+    }
 }
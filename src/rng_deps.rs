@@ -0,0 +1,232 @@
+//! 乱数の消費が依存するテーブルのスロットの解析。
+//!
+//! 乱数生成は `table[y]` と `table[x]` を読んで `table[x]` を上書きするだけなので、
+//! 一連の消費が読み書きするスロットはインデックスと回数だけから決まる。
+//! これを用いて、2 つの手の順序を入れ替えてよいか、先の手が後の手に影響しないかを判定できる。
+
+use crate::rng::{RngIndex, TABLE_LEN};
+
+/// 乱数テーブルのスロットの集合。
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct RngSlotSet([u64; 4]);
+
+impl RngSlotSet {
+    pub const fn new() -> Self {
+        Self([0; 4])
+    }
+
+    /// 全スロットからなる集合を返す。
+    pub fn full() -> Self {
+        (0..TABLE_LEN).collect()
+    }
+
+    pub fn len(self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == [0; 4]
+    }
+
+    pub fn contains(self, slot: usize) -> bool {
+        assert!(slot < TABLE_LEN);
+        (self.0[slot / 64] & (1 << (slot % 64))) != 0
+    }
+
+    /// スロットを追加する。元々含まれていなかったなら true を返す。
+    pub fn insert(&mut self, slot: usize) -> bool {
+        assert!(slot < TABLE_LEN);
+        let added = !self.contains(slot);
+        self.0[slot / 64] |= 1 << (slot % 64);
+        added
+    }
+
+    /// スロットを削除する。元々含まれていたなら true を返す。
+    pub fn remove(&mut self, slot: usize) -> bool {
+        assert!(slot < TABLE_LEN);
+        let removed = self.contains(slot);
+        self.0[slot / 64] &= !(1 << (slot % 64));
+        removed
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self(std::array::from_fn(|i| self.0[i] | other.0[i]))
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        Self(std::array::from_fn(|i| self.0[i] & other.0[i]))
+    }
+
+    pub fn difference(self, other: Self) -> Self {
+        Self(std::array::from_fn(|i| self.0[i] & !other.0[i]))
+    }
+
+    pub fn is_disjoint(self, other: Self) -> bool {
+        self.intersection(other).is_empty()
+    }
+
+    /// 含まれるスロットを昇順に列挙する。
+    pub fn iter(self) -> impl Iterator<Item = usize> {
+        (0..TABLE_LEN).filter(move |&slot| self.contains(slot))
+    }
+}
+
+impl FromIterator<usize> for RngSlotSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for slot in iter {
+            set.insert(slot);
+        }
+        set
+    }
+}
+
+/// 一連の乱数消費が読み書きするスロット。
+///
+/// インデックスは各手の開始時に指定されるものとし、ここではテーブルのみを考える。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct RngDeps {
+    reads: RngSlotSet,
+    writes: RngSlotSet,
+}
+
+impl RngDeps {
+    /// インデックス `index` から `len` 回乱数を消費するときの依存関係を返す。
+    pub fn of(index: u8, len: usize) -> Self {
+        let mut reads = RngSlotSet::new();
+        let mut writes = RngSlotSet::new();
+
+        let mut index = RngIndex::new(index);
+        // 全スロットを書き終えたらそれ以上は変化しない。
+        for _ in 0..len.min(2 * TABLE_LEN) {
+            for slot in [index.slot_y(), index.slot_x()] {
+                if !writes.contains(slot) {
+                    reads.insert(slot);
+                }
+            }
+            writes.insert(index.slot_x());
+            index = index.next();
+        }
+
+        Self { reads, writes }
+    }
+
+    /// 消費前の値が結果に影響するスロット。
+    pub fn reads(&self) -> RngSlotSet {
+        self.reads
+    }
+
+    /// 上書きされるスロット。
+    pub fn writes(&self) -> RngSlotSet {
+        self.writes
+    }
+
+    /// `self` の後に `next` を行う一連の消費の依存関係を返す。
+    pub fn then(&self, next: &Self) -> Self {
+        Self {
+            reads: self.reads.union(next.reads.difference(self.writes)),
+            writes: self.writes.union(next.writes),
+        }
+    }
+
+    /// `earlier` を先に行っても `self` の出力が変わらないなら true を返す。
+    pub fn unaffected_by(&self, earlier: &Self) -> bool {
+        earlier.writes.is_disjoint(self.reads)
+    }
+
+    /// `self` と `other` をどちらの順に行っても、両者の出力と最終的なテーブルが同じなら true を返す。
+    pub fn commutes_with(&self, other: &Self) -> bool {
+        self.unaffected_by(other)
+            && other.unaffected_by(self)
+            && self.writes.is_disjoint(other.writes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rng::Rng;
+
+    use super::*;
+
+    fn play(rng: &mut Rng, index: u8, len: usize) -> Vec<u8> {
+        rng.set_index(index);
+        rng.gen_iter().take(len).collect()
+    }
+
+    #[test]
+    fn test_slot_set() {
+        let mut set = RngSlotSet::new();
+        assert!(set.is_empty());
+        assert!(set.insert(0));
+        assert!(set.insert(249));
+        assert!(!set.insert(249));
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.iter().collect::<Vec<_>>(), [0, 249]);
+        assert!(set.remove(0));
+        assert!(!set.contains(0));
+
+        assert_eq!(RngSlotSet::full().len(), TABLE_LEN);
+        assert!(set.is_disjoint(RngSlotSet::full().difference(set)));
+    }
+
+    #[test]
+    fn test_deps_of() {
+        // 1 回だけならスロット y, x を読み、x を書く。
+        let deps = RngDeps::of(0, 1);
+        assert_eq!(deps.reads().iter().collect::<Vec<_>>(), [0, 103]);
+        assert_eq!(deps.writes().iter().collect::<Vec<_>>(), [103]);
+
+        // インデックス 103 が読むスロット 103 はインデックス 0 が書いたもの。
+        let deps = RngDeps::of(103, 1);
+        assert_eq!(deps.reads().iter().collect::<Vec<_>>(), [103, 206]);
+        let deps = RngDeps::of(0, 1).then(&deps);
+        assert_eq!(deps.reads().iter().collect::<Vec<_>>(), [0, 103, 206]);
+        assert!(!deps.unaffected_by(&RngDeps::of(0, 1)));
+        assert!(RngDeps::of(103, 1).unaffected_by(&RngDeps::of(1, 1)));
+
+        assert_eq!(
+            RngDeps::of(10, 3).then(&RngDeps::of(13, 4)),
+            RngDeps::of(10, 7)
+        );
+    }
+
+    #[test]
+    fn test_deps_against_simulation() {
+        let mut rng_base = Rng::new();
+        rng_base.fill(&mut [0; 1234]);
+
+        let moves: Vec<(u8, usize)> = RngIndex::all()
+            .step_by(7)
+            .flat_map(|index| [(index.get(), 3), (index.get(), 5)])
+            .collect();
+
+        for &(index_a, len_a) in &moves {
+            let deps_a = RngDeps::of(index_a, len_a);
+
+            for &(index_b, len_b) in &moves {
+                let deps_b = RngDeps::of(index_b, len_b);
+
+                if deps_b.unaffected_by(&deps_a) {
+                    let mut rng = rng_base.clone();
+                    let expected = play(&mut rng, index_b, len_b);
+                    let mut rng = rng_base.clone();
+                    play(&mut rng, index_a, len_a);
+                    assert_eq!(play(&mut rng, index_b, len_b), expected);
+                }
+
+                if deps_a.commutes_with(&deps_b) {
+                    let mut rng_ab = rng_base.clone();
+                    let outs_ab = (
+                        play(&mut rng_ab, index_a, len_a),
+                        play(&mut rng_ab, index_b, len_b),
+                    );
+                    let mut rng_ba = rng_base.clone();
+                    let outs_b = play(&mut rng_ba, index_b, len_b);
+                    let outs_a = play(&mut rng_ba, index_a, len_a);
+                    assert_eq!(outs_ab, (outs_a, outs_b));
+                    assert_eq!(rng_ab.table(), rng_ba.table());
+                }
+            }
+        }
+    }
+}
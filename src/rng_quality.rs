@@ -0,0 +1,452 @@
+//! ゲームでの使われ方 (`% n`) に即した乱数列の統計的検定。
+//!
+//! 開始インデックスごと、テーブルの局面 (初期状態からの消費回数) ごとに乱数列を生成し、
+//! 各ゲームと同じ方法で縮約した列に対してカイ二乗検定、系列相関検定、連の検定を行う。
+//! 各検定の結果は標準正規分布に従う z 値に換算して報告する。
+
+use crate::analysis::chi_square_uniform;
+use crate::rng::{Rng, RngIndex};
+use crate::trump::Deck;
+
+/// 乱数の縮約。縮約後の値は `0..modulus`。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct RngReduction {
+    pub name: &'static str,
+    pub modulus: u8,
+    pub kind: ReductionKind,
+}
+
+/// 乱数の縮約の方法。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ReductionKind {
+    /// `i` 番目の乱数 `r` を `(offsets[i % offsets.len()] + r) % modulus` に写す。
+    /// `wrapping` なら加算は 8 bit で桁あふれする。
+    Offset {
+        offsets: &'static [u8],
+        wrapping: bool,
+    },
+    /// `hand_len` 枚ごとに新しい山札から `Deck::deal()` で引き、カードの内部値に写す。
+    Deal { hand_len: usize },
+}
+
+impl RngReduction {
+    /// 各ゲームでの縮約。
+    ///
+    /// スロットマシンは各リールの基準位置を加えてから縮約する。乱数はリールの逆順に適用される。
+    /// ポーカーは 5 枚ごとに新しい山札から引く (引き直しは考慮しない)。
+    pub const GAMES: [Self; 4] = [
+        Self::with_offsets("fof", 64, &[16, 2, 10, 18], false),
+        Self::with_offsets("ms", 66, &[24, 10, 40], false),
+        Self::with_offsets("ror", 20, &[24, 10, 40], true),
+        Self::deal("poker", 5),
+    ];
+
+    /// 単純な縮約 `r % modulus`。
+    pub const fn new(name: &'static str, modulus: u8) -> Self {
+        Self::with_offsets(name, modulus, &[0], false)
+    }
+
+    pub const fn with_offsets(
+        name: &'static str,
+        modulus: u8,
+        offsets: &'static [u8],
+        wrapping: bool,
+    ) -> Self {
+        assert!(modulus >= 2);
+        assert!(!offsets.is_empty());
+        Self {
+            name,
+            modulus,
+            kind: ReductionKind::Offset { offsets, wrapping },
+        }
+    }
+
+    pub const fn deal(name: &'static str, hand_len: usize) -> Self {
+        assert!(hand_len >= 1 && hand_len <= 52);
+        Self {
+            name,
+            modulus: 52,
+            kind: ReductionKind::Deal { hand_len },
+        }
+    }
+
+    /// 乱数列 `rs` を縮約して `dst` に書き込む。
+    pub fn reduce(&self, rs: &[u8], dst: &mut [u8]) {
+        match self.kind {
+            ReductionKind::Offset { offsets, wrapping } => {
+                for (i, (dst, &r)) in dst.iter_mut().zip(rs).enumerate() {
+                    let offset = offsets[i % offsets.len()];
+                    *dst = if wrapping {
+                        offset.wrapping_add(r) % self.modulus
+                    } else {
+                        ((u16::from(offset) + u16::from(r)) % u16::from(self.modulus)) as u8
+                    };
+                }
+            }
+            ReductionKind::Deal { hand_len } => {
+                for (dst, rs) in dst.chunks_mut(hand_len).zip(rs.chunks(hand_len)) {
+                    let mut deck = Deck::new();
+                    for (dst, &r) in dst.iter_mut().zip(rs) {
+                        *dst = deck.deal(r).inner();
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 検定のパラメータ。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct QualityParams {
+    /// 各開始インデックスから生成する乱数の個数。
+    pub len: usize,
+    /// 局面の数。
+    pub phases: usize,
+    /// 局面の間の消費回数。
+    pub phase_gap: u64,
+}
+
+/// 1 つの (局面, 開始インデックス, 縮約) についての検定結果。
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QualityRow {
+    pub phase: usize,
+    pub index: u8,
+    pub reduction: RngReduction,
+    pub chi_square: f64,
+    pub chi_square_z: f64,
+    /// ラグ 1 の系列相関係数。列が定数なら定義されず `None`。
+    pub serial_correlation: Option<f64>,
+    pub serial_z: Option<f64>,
+    /// 中央値の上下による連の数。
+    pub runs: usize,
+    /// 中央値の上下の一方にしか値がなければ定義されず `None`。
+    pub runs_z: Option<f64>,
+}
+
+impl QualityRow {
+    /// 各検定の z 値の絶対値の最大。定義されない z 値は除く (カイ二乗検定の z 値は常に定義される)。
+    pub fn max_abs_z(&self) -> f64 {
+        [Some(self.chi_square_z), self.serial_z, self.runs_z]
+            .into_iter()
+            .flatten()
+            .map(f64::abs)
+            .fold(0.0, f64::max)
+    }
+}
+
+/// 検定結果の一覧。
+#[derive(Clone, Debug, PartialEq)]
+pub struct QualityReport {
+    pub params: QualityParams,
+    pub rows: Vec<QualityRow>,
+}
+
+impl QualityReport {
+    /// `rng` を初期状態として、各局面、各開始インデックス、各縮約について検定を行う。
+    ///
+    /// 系列相関を求めるため、`params.len` は 2 以上でなければならない。
+    pub fn generate(
+        rng: &Rng,
+        reductions: &[RngReduction],
+        params: QualityParams,
+    ) -> Result<Self, QualityError> {
+        if params.len < 2 {
+            return Err(QualityError::TooShort { len: params.len });
+        }
+
+        let mut rows = vec![];
+        let mut rng_phase = rng.clone();
+        let mut buf = vec![0; params.len];
+        let mut reduced = vec![0; params.len];
+
+        for phase in 0..params.phases {
+            if phase > 0 {
+                for _ in 0..params.phase_gap {
+                    rng_phase.gen();
+                }
+            }
+
+            for index in RngIndex::all() {
+                let mut rng = rng_phase.clone();
+                rng.set_index(index);
+                rng.fill(&mut buf);
+
+                for &reduction in reductions {
+                    reduction.reduce(&buf, &mut reduced);
+
+                    let (chi_square, chi_square_z) = chi_square(&reduced, reduction.modulus);
+                    let serial = serial_correlation(&reduced);
+                    let (runs, runs_z) = runs(&reduced, reduction.modulus);
+
+                    rows.push(QualityRow {
+                        phase,
+                        index: index.get(),
+                        reduction,
+                        chi_square,
+                        chi_square_z,
+                        serial_correlation: serial.map(|(r, _)| r),
+                        serial_z: serial.map(|(_, z)| z),
+                        runs,
+                        runs_z,
+                    });
+                }
+            }
+        }
+
+        Ok(Self { params, rows })
+    }
+
+    /// 全ての行を CSV で返す。定義されない値は空欄とする。
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "phase,index,reduction,modulus,chi_square,chi_square_z,serial_correlation,serial_z,runs,runs_z\n",
+        );
+
+        for row in &self.rows {
+            csv.push_str(&format!(
+                "{},{},{},{},{:.3},{:.3},{},{},{},{}\n",
+                row.phase,
+                row.index,
+                row.reduction.name,
+                row.reduction.modulus,
+                row.chi_square,
+                row.chi_square_z,
+                row.serial_correlation
+                    .map_or(String::new(), |r| format!("{r:.5}")),
+                row.serial_z.map_or(String::new(), |z| format!("{z:.3}")),
+                row.runs,
+                row.runs_z.map_or(String::new(), |z| format!("{z:.3}"))
+            ));
+        }
+
+        csv
+    }
+}
+
+impl std::fmt::Display for QualityReport {
+    /// (局面, 縮約) ごとに、|z| > 3 となった開始インデックスの数と最も偏ったインデックスを出力する。
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} draws per index, {} phases, {} draws between phases",
+            self.params.len, self.params.phases, self.params.phase_gap
+        )?;
+        writeln!(
+            f,
+            "phase\treduction\t|z|>3\tworst index\tchi2 z\tserial z\truns z"
+        )?;
+
+        let mut groups: Vec<(usize, RngReduction)> = vec![];
+        for row in &self.rows {
+            if !groups.contains(&(row.phase, row.reduction)) {
+                groups.push((row.phase, row.reduction));
+            }
+        }
+
+        for (phase, reduction) in groups {
+            let rows = || {
+                self.rows
+                    .iter()
+                    .filter(move |row| row.phase == phase && row.reduction == reduction)
+            };
+            let outlier_count = rows().filter(|row| row.max_abs_z() > 3.0).count();
+            let worst = rows()
+                .max_by(|lhs, rhs| lhs.max_abs_z().total_cmp(&rhs.max_abs_z()))
+                .unwrap();
+
+            writeln!(
+                f,
+                "{phase}\t{} (%{})\t{outlier_count}\t{}\t{:+.2}\t{}\t{}",
+                reduction.name,
+                reduction.modulus,
+                worst.index,
+                worst.chi_square_z,
+                worst
+                    .serial_z
+                    .map_or("-".to_owned(), |z| format!("{z:+.2}")),
+                worst.runs_z.map_or("-".to_owned(), |z| format!("{z:+.2}"))
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// 検定のエラー。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum QualityError {
+    /// 乱数列が短すぎる。
+    TooShort { len: usize },
+}
+
+impl std::fmt::Display for QualityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooShort { len } => {
+                write!(
+                    f,
+                    "sequence length {len} is too short (at least 2 is needed)"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for QualityError {}
+
+/// 一様分布に対するカイ二乗値と、その z 値 (Wilson-Hilferty 近似) を返す。
+fn chi_square(xs: &[u8], modulus: u8) -> (f64, f64) {
+    let mut counts = vec![0_u64; usize::from(modulus)];
+    for &x in xs {
+        counts[usize::from(x)] += 1;
+    }
+
+    let chi_square = chi_square_uniform(&counts).expect("empty sequence");
+
+    let dof = f64::from(modulus - 1);
+    let v = 2.0 / (9.0 * dof);
+    let z = ((chi_square / dof).cbrt() - (1.0 - v)) / v.sqrt();
+
+    (chi_square, z)
+}
+
+/// ラグ 1 の系列相関係数と、その z 値 (`r * sqrt(n)`) を返す。
+///
+/// 前後の列のいずれかが定数なら相関係数は定義されないので `None` を返す。
+fn serial_correlation(xs: &[u8]) -> Option<(f64, f64)> {
+    let n = xs.len() - 1;
+    let (lhs, rhs) = (&xs[..n], &xs[1..]);
+
+    let mean = |ys: &[u8]| ys.iter().map(|&y| f64::from(y)).sum::<f64>() / n as f64;
+    let (mean_lhs, mean_rhs) = (mean(lhs), mean(rhs));
+
+    let mut cov = 0.0;
+    let mut var_lhs = 0.0;
+    let mut var_rhs = 0.0;
+    for (&a, &b) in lhs.iter().zip(rhs) {
+        let (da, db) = (f64::from(a) - mean_lhs, f64::from(b) - mean_rhs);
+        cov += da * db;
+        var_lhs += da * da;
+        var_rhs += db * db;
+    }
+
+    if var_lhs == 0.0 || var_rhs == 0.0 {
+        return None;
+    }
+
+    let r = cov / (var_lhs * var_rhs).sqrt();
+    Some((r, r * (n as f64).sqrt()))
+}
+
+/// 中央値 `(modulus - 1) / 2` の上下による連の数と、その z 値 (Wald-Wolfowitz) を返す。
+///
+/// 中央値の上下の一方にしか値がなければ z 値は定義されないので `None` とする。
+fn runs(xs: &[u8], modulus: u8) -> (usize, Option<f64>) {
+    // 2 倍して比較し、中央値ちょうどの値は除く。
+    let median2 = u16::from(modulus - 1);
+    let signs: Vec<bool> = xs
+        .iter()
+        .map(|&x| 2 * u16::from(x))
+        .filter(|&x2| x2 != median2)
+        .map(|x2| x2 > median2)
+        .collect();
+
+    if signs.is_empty() {
+        return (0, None);
+    }
+    let runs = 1 + signs.windows(2).filter(|w| w[0] != w[1]).count();
+
+    let n1 = signs.iter().filter(|&&s| s).count() as f64;
+    let n2 = signs.len() as f64 - n1;
+    if n1 == 0.0 || n2 == 0.0 {
+        return (runs, None);
+    }
+    let n = n1 + n2;
+    let mu = 2.0 * n1 * n2 / n + 1.0;
+    let var = (mu - 1.0) * (mu - 2.0) / (n - 1.0);
+
+    (runs, Some((runs as f64 - mu) / var.sqrt()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statistics() {
+        let uniform: Vec<u8> = (0..1000).map(|i| (i % 10) as u8).collect();
+        assert_eq!(chi_square(&uniform, 10).0, 0.0);
+
+        let alternating: Vec<u8> = (0..1000).map(|i| (i % 2) as u8).collect();
+        assert!((serial_correlation(&alternating).unwrap().0 + 1.0).abs() < 1e-9);
+        let (runs_count, runs_z) = runs(&alternating, 2);
+        assert_eq!(runs_count, 1000);
+        assert!(runs_z.unwrap() > 10.0);
+
+        let blocks: Vec<u8> = (0..1000).map(|i| (i / 500) as u8).collect();
+        assert_eq!(runs(&blocks, 2).0, 2);
+
+        // 定数列や中央値ちょうどの値だけの列では、系列相関と連の z 値は定義されない。
+        let constant = [3; 1000];
+        assert_eq!(serial_correlation(&constant), None);
+        assert_eq!(runs(&constant, 10), (1, None));
+        assert_eq!(runs(&constant, 7), (0, None));
+    }
+
+    #[test]
+    fn test_report() {
+        let params = QualityParams {
+            len: 2000,
+            phases: 2,
+            phase_gap: 10000,
+        };
+        let report = QualityReport::generate(&Rng::new(), &RngReduction::GAMES, params).unwrap();
+        assert_eq!(report.rows.len(), 2 * 256 * 4);
+
+        // 実際の乱数列は極端には偏っていない。
+        let outlier_count = report
+            .rows
+            .iter()
+            .filter(|row| row.max_abs_z() > 6.0)
+            .count();
+        assert!(outlier_count < report.rows.len() / 100);
+
+        assert_eq!(report.to_csv().lines().count(), report.rows.len() + 1);
+        assert_eq!(report.to_string().lines().count(), 2 + 2 * 4);
+
+        for len in [0, 1] {
+            let params = QualityParams { len, ..params };
+            assert_eq!(
+                QualityReport::generate(&Rng::new(), &RngReduction::GAMES, params),
+                Err(QualityError::TooShort { len })
+            );
+        }
+    }
+
+    #[test]
+    fn test_reductions_match_games() {
+        use crate::bandit::riches_of_rome;
+
+        let [_, _, ror, poker] = RngReduction::GAMES;
+
+        // "Riches of Rome" のリール位置は縮約後の値の 2 倍 (リールは乱数の逆順)。
+        for r in 0..=0xFF_u8 {
+            let rs = [r, r.wrapping_mul(3), r ^ 0xA5];
+            let mut reduced = [0; 3];
+            ror.reduce(&rs, &mut reduced);
+            let reels = riches_of_rome::randoms_to_reels(&riches_of_rome::REEL_BASES, rs);
+            assert_eq!(reduced, [reels[2] / 2, reels[1] / 2, reels[0] / 2]);
+        }
+
+        // ポーカーは 1 手ごとに新しい山札から引いたカード。
+        let mut rng = Rng::new();
+        let rs: Vec<u8> = (0..12).map(|_| rng.gen()).collect();
+        let mut reduced = [0; 12];
+        poker.reduce(&rs, &mut reduced);
+        for (reduced, rs) in reduced.chunks(5).zip(rs.chunks(5)) {
+            let mut deck = Deck::new();
+            let cards: Vec<_> = rs.iter().map(|&r| deck.deal(r).inner()).collect();
+            assert_eq!(reduced, cards);
+        }
+    }
+}
@@ -0,0 +1,655 @@
+//! iNES 形式の ROM ファイルからのテーブル抽出。
+//!
+//! 本クレートのテーブルは全て手作業で書き写したものなので、実際のカートリッジと照合できるようにする。
+//! ダンプは CRC32 で同定し、既知のダンプごとに記録した固定オフセットからテーブルを抽出して比較する。
+//! 未知のダンプは、テキスト形式で与えた配置で比較できる。
+
+use std::collections::BTreeMap;
+
+use crate::bandit::{fountains_of_fortune, magnicent_sevens, riches_of_rome};
+use crate::bandit::{BanditFofSymbol, BanditMsSymbol, BanditRorSymbol};
+use crate::game_version::PayTables;
+use crate::rng::{Rng, TABLE_LEN};
+
+/// iNES 形式の ROM。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Rom {
+    mapper: u8,
+    prg: Vec<u8>,
+    chr: Vec<u8>,
+}
+
+impl Rom {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RomError> {
+        const PRG_BANK_LEN: usize = 0x4000;
+        const CHR_BANK_LEN: usize = 0x2000;
+        const TRAINER_LEN: usize = 0x200;
+
+        let header = bytes.get(..16).ok_or(RomError::NotInes)?;
+        if &header[..4] != b"NES\x1A" {
+            return Err(RomError::NotInes);
+        }
+
+        let prg_len = PRG_BANK_LEN * usize::from(header[4]);
+        let chr_len = CHR_BANK_LEN * usize::from(header[5]);
+        let has_trainer = (header[6] & 4) != 0;
+        let mapper = (header[7] & 0xF0) | (header[6] >> 4);
+
+        let body = &bytes[16..];
+        let body = if has_trainer {
+            body.get(TRAINER_LEN..).ok_or(RomError::Truncated)?
+        } else {
+            body
+        };
+
+        let prg = body.get(..prg_len).ok_or(RomError::Truncated)?.to_vec();
+        let chr = body
+            .get(prg_len..prg_len + chr_len)
+            .ok_or(RomError::Truncated)?
+            .to_vec();
+
+        Ok(Self { mapper, prg, chr })
+    }
+
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, RomError> {
+        let bytes = std::fs::read(path).map_err(RomError::Io)?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn mapper(&self) -> u8 {
+        self.mapper
+    }
+
+    pub fn prg(&self) -> &[u8] {
+        &self.prg
+    }
+
+    pub fn chr(&self) -> &[u8] {
+        &self.chr
+    }
+
+    /// PRG と CHR を連結したもの (ヘッダを除く) の CRC32 を返す。
+    pub fn crc32(&self) -> u32 {
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&self.prg);
+        hasher.update(&self.chr);
+        hasher.finalize()
+    }
+
+    /// CRC32 が一致する既知のダンプを返す。
+    pub fn known_dump(&self) -> Option<&'static KnownDump> {
+        let crc32 = self.crc32();
+        KNOWN_DUMPS.iter().find(|dump| dump.crc32 == crc32)
+    }
+
+    /// 指定した配置でテーブルたちを抽出する。
+    pub fn extract_tables(&self, layout: &RomLayout) -> Result<RomTables, RomError> {
+        RomTables::extract(&self.prg, layout)
+    }
+
+    /// 既知のダンプとしてテーブルたちを抽出し、本クレートが持つテーブルとの差分を返す。
+    pub fn verify_tables(&self) -> Result<Vec<TableDiff>, RomError> {
+        let dump = self
+            .known_dump()
+            .ok_or(RomError::UnknownDump(self.crc32()))?;
+        self.verify_tables_at(&dump.layout)
+    }
+
+    /// 指定した配置でテーブルたちを抽出し、本クレートが持つテーブルとの差分を返す。
+    ///
+    /// `KNOWN_DUMPS` に載っていないダンプの配置を確かめる際に使う。
+    pub fn verify_tables_at(&self, layout: &RomLayout) -> Result<Vec<TableDiff>, RomError> {
+        let actual = self.extract_tables(layout)?;
+        Ok(RomTables::default().diff(&actual, layout))
+    }
+}
+
+/// 既知のダンプ。
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct KnownDump {
+    pub name: &'static str,
+    /// PRG と CHR を連結したもの (ヘッダを除く) の CRC32。
+    pub crc32: u32,
+    pub layout: RomLayout,
+    /// このダンプの賞金倍率 (ROM から読み取らず、ここに記録する)。
+    pub pay_tables: PayTables,
+    /// 乱数ルーチンの CPU アドレス。
+    pub rng_routine: u16,
+    /// 乱数ルーチンの実行時に $8000-$BFFF に置かれる PRG バンク。
+    pub rng_routine_bank: usize,
+}
+
+/// テーブルの配置を確認済みのダンプたち。
+///
+/// 実際のカートリッジでハッシュ値とオフセットを確認できたものだけを載せる。
+/// 現状は確認済みのものがないため空。それまでは配置をテキスト形式 (`RomLayout` を参照) で与え、
+/// `Rom::verify_tables_at()` で照合する。
+pub const KNOWN_DUMPS: &[KnownDump] = &[];
+
+/// PRG 中のテーブルの配置 (PRG 先頭からのオフセット)。
+///
+/// テキスト形式は `RomLayout::offsets()` の名前をキーとする以下の形式 (オフセットは例):
+///
+/// ```text
+/// rng_table_ini     = 0x1234
+/// fof_reels[0]      = 0x2000
+/// # ... 以下全てのテーブルについて同様。
+/// ror_symbols[2]    = 0x4128
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RomLayout {
+    pub rng_table_ini: usize,
+    pub fof_reels: [usize; 2],
+    pub fof_symbols: [usize; 2],
+    pub ms_reels: [usize; 3],
+    pub ms_symbols: usize,
+    pub ror_reel_bases: [usize; 3],
+    pub ror_symbols: [usize; 3],
+}
+
+impl RomLayout {
+    /// 各テーブルの名前とオフセット。`RomTables::to_bytes()` と同じ順。
+    pub(crate) fn offsets(&self) -> [(&'static str, usize); 15] {
+        [
+            ("rng_table_ini", self.rng_table_ini),
+            ("fof_reels[0]", self.fof_reels[0]),
+            ("fof_reels[1]", self.fof_reels[1]),
+            ("fof_symbols[0]", self.fof_symbols[0]),
+            ("fof_symbols[1]", self.fof_symbols[1]),
+            ("ms_reels[0]", self.ms_reels[0]),
+            ("ms_reels[1]", self.ms_reels[1]),
+            ("ms_reels[2]", self.ms_reels[2]),
+            ("ms_symbols", self.ms_symbols),
+            ("ror_reel_bases[0]", self.ror_reel_bases[0]),
+            ("ror_reel_bases[1]", self.ror_reel_bases[1]),
+            ("ror_reel_bases[2]", self.ror_reel_bases[2]),
+            ("ror_symbols[0]", self.ror_symbols[0]),
+            ("ror_symbols[1]", self.ror_symbols[1]),
+            ("ror_symbols[2]", self.ror_symbols[2]),
+        ]
+    }
+}
+
+impl std::str::FromStr for RomLayout {
+    type Err = ParseRomLayoutError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut offsets = BTreeMap::<String, usize>::new();
+
+        for (i, line) in s.lines().enumerate() {
+            let lineno = i + 1;
+            let err = |message: String| ParseRomLayoutError { lineno, message };
+
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| err("expected KEY = VALUE".to_owned()))?;
+            let (key, value) = (key.trim(), value.trim());
+
+            let offset = value
+                .strip_prefix("0x")
+                .map_or_else(|| value.parse(), |hex| usize::from_str_radix(hex, 16))
+                .map_err(|_| err(format!("invalid PRG offset: {value:?}")))?;
+            if offsets.insert(key.to_owned(), offset).is_some() {
+                return Err(err(format!("duplicate key: {key}")));
+            }
+        }
+
+        let mut layout = Self {
+            rng_table_ini: 0,
+            fof_reels: [0; 2],
+            fof_symbols: [0; 2],
+            ms_reels: [0; 3],
+            ms_symbols: 0,
+            ror_reel_bases: [0; 3],
+            ror_symbols: [0; 3],
+        };
+        for (name, _) in layout.offsets() {
+            let offset = offsets.remove(name).ok_or_else(|| ParseRomLayoutError {
+                lineno: 0,
+                message: format!("missing key: {name}"),
+            })?;
+            *layout.offset_mut(name) = offset;
+        }
+        if let Some(key) = offsets.into_keys().next() {
+            return Err(ParseRomLayoutError {
+                lineno: 0,
+                message: format!("unknown key: {key}"),
+            });
+        }
+
+        Ok(layout)
+    }
+}
+
+impl RomLayout {
+    fn offset_mut(&mut self, name: &str) -> &mut usize {
+        match name {
+            "rng_table_ini" => &mut self.rng_table_ini,
+            "fof_reels[0]" => &mut self.fof_reels[0],
+            "fof_reels[1]" => &mut self.fof_reels[1],
+            "fof_symbols[0]" => &mut self.fof_symbols[0],
+            "fof_symbols[1]" => &mut self.fof_symbols[1],
+            "ms_reels[0]" => &mut self.ms_reels[0],
+            "ms_reels[1]" => &mut self.ms_reels[1],
+            "ms_reels[2]" => &mut self.ms_reels[2],
+            "ms_symbols" => &mut self.ms_symbols,
+            "ror_reel_bases[0]" => &mut self.ror_reel_bases[0],
+            "ror_reel_bases[1]" => &mut self.ror_reel_bases[1],
+            "ror_reel_bases[2]" => &mut self.ror_reel_bases[2],
+            "ror_symbols[0]" => &mut self.ror_symbols[0],
+            "ror_symbols[1]" => &mut self.ror_symbols[1],
+            "ror_symbols[2]" => &mut self.ror_symbols[2],
+            _ => unreachable!("unknown table: {name}"),
+        }
+    }
+}
+
+/// カートリッジによって異なりうるテーブルたち。
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RomTables {
+    /// 乱数テーブルの初期値。
+    pub rng_table_ini: [u8; TABLE_LEN],
+    /// "Fountains of Fortune" の乱数からリール位置への変換テーブル。
+    pub fof_reels: [[u8; 64]; 2],
+    /// "Fountains of Fortune" のリール位置からシンボルへの変換テーブル。
+    pub fof_symbols: [[BanditFofSymbol; 11]; 2],
+    /// "Magnificent Sevens" の乱数からリール位置への変換テーブル。
+    pub ms_reels: [[u8; 66]; 3],
+    /// "Magnificent Sevens" のリール位置からシンボルへの変換テーブル。
+    pub ms_symbols: [BanditMsSymbol; 22],
+    /// "Riches of Rome" の各リールの乱数に加算される基準値。
+    pub ror_reel_bases: [u8; 3],
+    /// "Riches of Rome" のリール位置からシンボルへの変換テーブル。
+    pub ror_symbols: [[BanditRorSymbol; 20]; 3],
+}
+
+impl Default for RomTables {
+    /// 本クレートが持つ (書き写した) テーブルたちを返す。
+    fn default() -> Self {
+        Self {
+            rng_table_ini: Rng::TABLE_INI,
+            fof_reels: fountains_of_fortune::REEL_TABLES,
+            fof_symbols: fountains_of_fortune::SYMBOL_TABLES,
+            ms_reels: magnicent_sevens::REEL_TABLES,
+            ms_symbols: magnicent_sevens::SYMBOL_TABLE,
+            ror_reel_bases: riches_of_rome::REEL_BASES,
+            ror_symbols: riches_of_rome::SYMBOL_TABLES,
+        }
+    }
+}
+
+impl RomTables {
+    pub fn extract(prg: &[u8], layout: &RomLayout) -> Result<Self, RomError> {
+        fn read<const N: usize>(
+            prg: &[u8],
+            name: &'static str,
+            offset: usize,
+        ) -> Result<[u8; N], RomError> {
+            prg.get(offset..)
+                .and_then(|rest| rest.get(..N))
+                .map(|bytes| bytes.try_into().unwrap())
+                .ok_or(RomError::TableOutOfRange(name))
+        }
+
+        fn read_symbols<T: SymbolCode, const N: usize>(
+            prg: &[u8],
+            name: &'static str,
+            offset: usize,
+        ) -> Result<[T; N], RomError> {
+            let codes: [u8; N] = read(prg, name, offset)?;
+            let mut symbols = [T::ALL[0]; N];
+            for (i, (symbol, code)) in symbols.iter_mut().zip(codes).enumerate() {
+                *symbol = T::from_code(code).ok_or(RomError::InvalidSymbol {
+                    table: name,
+                    offset: offset + i,
+                    code,
+                })?;
+            }
+            Ok(symbols)
+        }
+
+        let [base0, base1, base2]: [[u8; 1]; 3] = [
+            read(prg, "ror_reel_bases[0]", layout.ror_reel_bases[0])?,
+            read(prg, "ror_reel_bases[1]", layout.ror_reel_bases[1])?,
+            read(prg, "ror_reel_bases[2]", layout.ror_reel_bases[2])?,
+        ];
+
+        Ok(Self {
+            rng_table_ini: read(prg, "rng_table_ini", layout.rng_table_ini)?,
+            fof_reels: [
+                read(prg, "fof_reels[0]", layout.fof_reels[0])?,
+                read(prg, "fof_reels[1]", layout.fof_reels[1])?,
+            ],
+            fof_symbols: [
+                read_symbols(prg, "fof_symbols[0]", layout.fof_symbols[0])?,
+                read_symbols(prg, "fof_symbols[1]", layout.fof_symbols[1])?,
+            ],
+            ms_reels: [
+                read(prg, "ms_reels[0]", layout.ms_reels[0])?,
+                read(prg, "ms_reels[1]", layout.ms_reels[1])?,
+                read(prg, "ms_reels[2]", layout.ms_reels[2])?,
+            ],
+            ms_symbols: read_symbols(prg, "ms_symbols", layout.ms_symbols)?,
+            ror_reel_bases: [base0[0], base1[0], base2[0]],
+            ror_symbols: [
+                read_symbols(prg, "ror_symbols[0]", layout.ror_symbols[0])?,
+                read_symbols(prg, "ror_symbols[1]", layout.ror_symbols[1])?,
+                read_symbols(prg, "ror_symbols[2]", layout.ror_symbols[2])?,
+            ],
+        })
+    }
+
+    /// 各テーブルの ROM 上でのバイト列。`RomLayout::offsets()` と同じ順。
+    pub(crate) fn to_bytes(&self) -> [Vec<u8>; 15] {
+        fn codes<T: SymbolCode>(symbols: &[T]) -> Vec<u8> {
+            symbols.iter().map(|&symbol| symbol.code()).collect()
+        }
+
+        [
+            self.rng_table_ini.to_vec(),
+            self.fof_reels[0].to_vec(),
+            self.fof_reels[1].to_vec(),
+            codes(&self.fof_symbols[0]),
+            codes(&self.fof_symbols[1]),
+            self.ms_reels[0].to_vec(),
+            self.ms_reels[1].to_vec(),
+            self.ms_reels[2].to_vec(),
+            codes(&self.ms_symbols),
+            vec![self.ror_reel_bases[0]],
+            vec![self.ror_reel_bases[1]],
+            vec![self.ror_reel_bases[2]],
+            codes(&self.ror_symbols[0]),
+            codes(&self.ror_symbols[1]),
+            codes(&self.ror_symbols[2]),
+        ]
+    }
+
+    /// `self` を期待値として `actual` と比較し、一致しないバイトを全て返す。
+    ///
+    /// `layout` は `actual` の抽出元の配置で、差分の PRG オフセットの計算に使う。
+    pub fn diff(&self, actual: &Self, layout: &RomLayout) -> Vec<TableDiff> {
+        let mut diffs = vec![];
+
+        for (((table, offset), expected), actual) in layout
+            .offsets()
+            .into_iter()
+            .zip(self.to_bytes())
+            .zip(actual.to_bytes())
+        {
+            for (index, (expected, actual)) in expected.into_iter().zip(actual).enumerate() {
+                if expected != actual {
+                    diffs.push(TableDiff {
+                        table,
+                        index,
+                        prg_offset: offset + index,
+                        expected,
+                        actual,
+                    });
+                }
+            }
+        }
+
+        diffs
+    }
+}
+
+/// テーブルの 1 バイトの差分。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct TableDiff {
+    pub table: &'static str,
+    /// テーブル内のインデックス。
+    pub index: usize,
+    pub prg_offset: usize,
+    /// 本クレートが持つ値。
+    pub expected: u8,
+    /// ROM 上の値。
+    pub actual: u8,
+}
+
+impl std::fmt::Display for TableDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}[{}] (PRG 0x{:05X}): expected 0x{:02X}, found 0x{:02X}",
+            self.table, self.index, self.prg_offset, self.expected, self.actual
+        )
+    }
+}
+
+/// ROM 上でのシンボルの番号。
+///
+/// 番号は列挙子の宣言順とした推測であり、実際のカートリッジでは確認していない。
+/// 推測が誤っていれば、抽出時の `RomError::InvalidSymbol` か `TableDiff` として現れる。
+trait SymbolCode: Copy + Eq + 'static {
+    const ALL: &'static [Self];
+
+    fn code(self) -> u8 {
+        Self::ALL.iter().position(|&symbol| symbol == self).unwrap() as u8
+    }
+
+    fn from_code(code: u8) -> Option<Self> {
+        Self::ALL.get(usize::from(code)).copied()
+    }
+}
+
+impl SymbolCode for BanditFofSymbol {
+    const ALL: &'static [Self] = {
+        use BanditFofSymbol::*;
+        &[Null, Cherry, RedSeven, GreenSeven, BlackSeven]
+    };
+}
+
+impl SymbolCode for BanditMsSymbol {
+    const ALL: &'static [Self] = {
+        use BanditMsSymbol::*;
+        &[Blank, BlackSeven, RedSeven, SingleBar, DoubleBar, TripleBar]
+    };
+}
+
+impl SymbolCode for BanditRorSymbol {
+    const ALL: &'static [Self] = {
+        use BanditRorSymbol::*;
+        &[Watermelon, Lemon, Bar, Seven, Cherry, Orange, Plum, Bell]
+    };
+}
+
+/// `RomLayout` のパースエラー。`lineno` は 1-based (0 はファイル全体に関するエラー)。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseRomLayoutError {
+    pub lineno: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseRomLayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.lineno == 0 {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "line {}: {}", self.lineno, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ParseRomLayoutError {}
+
+/// ROM の読み込みエラー。
+#[derive(Debug)]
+pub enum RomError {
+    Io(std::io::Error),
+    NotInes,
+    Truncated,
+    /// CRC32 が既知のダンプのいずれとも一致しない。
+    UnknownDump(u32),
+    TableOutOfRange(&'static str),
+    /// シンボルの変換テーブルに未知の番号がある。
+    InvalidSymbol {
+        table: &'static str,
+        offset: usize,
+        code: u8,
+    },
+}
+
+impl std::fmt::Display for RomError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "cannot read ROM: {e}"),
+            Self::NotInes => f.write_str("not an iNES file"),
+            Self::Truncated => f.write_str("ROM is truncated"),
+            Self::UnknownDump(crc32) => write!(f, "unknown ROM dump (CRC32: {crc32:08X})"),
+            Self::TableOutOfRange(name) => write!(f, "table is out of PRG range: {name}"),
+            Self::InvalidSymbol {
+                table,
+                offset,
+                code,
+            } => write!(
+                f,
+                "unknown symbol code 0x{code:02X} in {table} (PRG 0x{offset:05X})"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RomError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// 環境変数 `CAESARS_PALACE_ROM` で指定された ROM を読み込む。未指定なら `None`。
+    pub(crate) fn load_user_rom() -> Option<Rom> {
+        let path = std::env::var_os("CAESARS_PALACE_ROM")?;
+        Some(Rom::load(path).expect("cannot load CAESARS_PALACE_ROM"))
+    }
+
+    fn synthetic_ines(prg: &[u8], trainer: bool) -> Vec<u8> {
+        let mut bytes = b"NES\x1A".to_vec();
+        bytes.extend([
+            (prg.len() / 0x4000) as u8,
+            1,
+            if trainer { 4 } else { 0 },
+            0,
+        ]);
+        bytes.extend([0; 8]);
+        if trainer {
+            bytes.extend([0xEE; 0x200]);
+        }
+        bytes.extend(prg);
+        bytes.extend([0xCC; 0x2000]);
+        bytes
+    }
+
+    #[test]
+    fn test_extract_synthetic() {
+        let tables = RomTables::default();
+
+        let layout = RomLayout {
+            rng_table_ini: 0x1234,
+            fof_reels: [0x2000, 0x2040],
+            fof_symbols: [0x2080, 0x208B],
+            ms_reels: [0x3000, 0x3042, 0x3084],
+            ms_symbols: 0x30C6,
+            ror_reel_bases: [0x4000, 0x4010, 0x4020],
+            ror_symbols: [0x4100, 0x4114, 0x4128],
+        };
+        let mut prg = vec![0xFF; 0x8000];
+        for ((_, offset), bytes) in layout.offsets().into_iter().zip(tables.to_bytes()) {
+            prg[offset..][..bytes.len()].copy_from_slice(&bytes);
+        }
+
+        for trainer in [false, true] {
+            let rom = Rom::from_bytes(&synthetic_ines(&prg, trainer)).unwrap();
+            assert_eq!(rom.prg(), prg);
+            assert_eq!(rom.chr().len(), 0x2000);
+
+            let actual = rom.extract_tables(&layout).unwrap();
+            assert_eq!(actual, tables);
+            assert_eq!(tables.diff(&actual, &layout), []);
+            assert!(rom.known_dump().is_none());
+            assert!(matches!(rom.verify_tables(), Err(RomError::UnknownDump(_))));
+            assert_eq!(rom.verify_tables_at(&layout).unwrap(), []);
+        }
+
+        // テキスト形式の配置。
+        let text: String = layout
+            .offsets()
+            .into_iter()
+            .map(|(name, offset)| format!("{name} = 0x{offset:X}  # comment\n"))
+            .collect();
+        assert_eq!(text.parse::<RomLayout>(), Ok(layout.clone()));
+        assert_eq!(
+            "rng_table_ini = 0x10"
+                .parse::<RomLayout>()
+                .unwrap_err()
+                .message,
+            "missing key: fof_reels[0]"
+        );
+
+        // 差分はバイト単位で PRG オフセットとともに報告される。
+        prg[0x1234 + 5] ^= 1;
+        prg[0x4010] = 11;
+        let actual = RomTables::extract(&prg, &layout).unwrap();
+        assert_eq!(
+            tables.diff(&actual, &layout),
+            [
+                TableDiff {
+                    table: "rng_table_ini",
+                    index: 5,
+                    prg_offset: 0x1239,
+                    expected: Rng::TABLE_INI[5],
+                    actual: Rng::TABLE_INI[5] ^ 1,
+                },
+                TableDiff {
+                    table: "ror_reel_bases[1]",
+                    index: 0,
+                    prg_offset: 0x4010,
+                    expected: 10,
+                    actual: 11,
+                },
+            ]
+        );
+
+        prg[0x30C6] = 6;
+        assert!(matches!(
+            RomTables::extract(&prg, &layout),
+            Err(RomError::InvalidSymbol {
+                table: "ms_symbols",
+                offset: 0x30C6,
+                code: 6
+            })
+        ));
+
+        assert!(matches!(
+            Rom::from_bytes(b"NES\x1A\x02\x01\0\0\0\0\0\0\0\0\0\0"),
+            Err(RomError::Truncated)
+        ));
+        assert!(matches!(Rom::from_bytes(b"NES"), Err(RomError::NotInes)));
+    }
+
+    /// 書き写したテーブルが実際のカートリッジと一致することを確認する。
+    /// 環境変数 `CAESARS_PALACE_ROM` に既知のダンプを指定して `--ignored` で実行する。
+    #[test]
+    #[ignore]
+    fn test_tables_match_user_rom() {
+        let rom = load_user_rom().expect("CAESARS_PALACE_ROM is not set");
+
+        let diffs = rom
+            .verify_tables()
+            .unwrap_or_else(|e| panic!("ROM (CRC32: {:08X}): {e}", rom.crc32()));
+        if !diffs.is_empty() {
+            let report: Vec<_> = diffs.iter().map(ToString::to_string).collect();
+            panic!("tables differ from the ROM:\n{}", report.join("\n"));
+        }
+    }
+}
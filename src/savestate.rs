@@ -0,0 +1,401 @@
+//! エミュレータのステートセーブファイルの読み込み。
+
+use std::io::Read as _;
+
+use crate::money::Money;
+use crate::ram::{RamMap, WorkRam};
+use crate::rng::Rng;
+
+/// ステートセーブファイルの形式。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SavestateFormat {
+    /// FCEUX (`.fcs`)。
+    Fceux,
+    /// Mesen (`.mss`)。
+    Mesen,
+}
+
+impl SavestateFormat {
+    /// ファイル先頭のマジックナンバーから形式を判定する。
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"FCSX") {
+            Some(Self::Fceux)
+        } else if bytes.starts_with(b"MSS") {
+            Some(Self::Mesen)
+        } else {
+            None
+        }
+    }
+}
+
+/// ステートセーブファイルから内部 RAM (2 KiB) を取り出す。形式は自動判定する。
+pub fn read_savestate_ram(bytes: &[u8]) -> Result<WorkRam, SavestateError> {
+    match SavestateFormat::detect(bytes) {
+        Some(SavestateFormat::Fceux) => read_fceux_ram(bytes),
+        Some(SavestateFormat::Mesen) => read_mesen_ram(bytes),
+        None => Err(SavestateError::UnknownFormat),
+    }
+}
+
+/// FCEUX のステートセーブファイルから内部 RAM を取り出す。
+///
+/// 16 byte のヘッダ ("FCSX", 展開後サイズ, バージョン, 圧縮後サイズ) の後に
+/// (zlib 圧縮された) セクション列が続く。
+/// 各セクションは (種別, サイズ, チャンク列) からなり、各チャンクは (4 文字の名前, サイズ, データ) からなる。
+/// 内部 RAM は CPU セクション (種別 1) の "RAM\0" チャンクに入っている。
+pub fn read_fceux_ram(bytes: &[u8]) -> Result<WorkRam, SavestateError> {
+    const SECTION_CPU: u8 = 1;
+
+    if !bytes.starts_with(b"FCSX") || bytes.len() < 16 {
+        return Err(SavestateError::UnknownFormat);
+    }
+
+    let len = read_u32_le(&bytes[4..]) as usize;
+    let len_compressed = read_u32_le(&bytes[12..]);
+    let body = &bytes[16..];
+
+    let data = if len_compressed == u32::MAX {
+        body.get(..len).ok_or(SavestateError::Truncated)?.to_vec()
+    } else {
+        let compressed = body
+            .get(..len_compressed as usize)
+            .ok_or(SavestateError::Truncated)?;
+        inflate(compressed)?
+    };
+
+    let mut sections = &data[..];
+    while !sections.is_empty() {
+        let (&kind, rest) = sections.split_first().unwrap();
+        let size = read_u32_le(rest.get(..4).ok_or(SavestateError::Truncated)?) as usize;
+        let section = rest.get(4..4 + size).ok_or(SavestateError::Truncated)?;
+        sections = &rest[4 + size..];
+
+        if kind != SECTION_CPU {
+            continue;
+        }
+
+        let mut chunks = section;
+        while !chunks.is_empty() {
+            let header = chunks.get(..8).ok_or(SavestateError::Truncated)?;
+            let size = read_u32_le(&header[4..]) as usize;
+            let chunk = chunks.get(8..8 + size).ok_or(SavestateError::Truncated)?;
+            chunks = &chunks[8 + size..];
+
+            if &header[..4] == b"RAM\0" {
+                return chunk.try_into().map_err(|_| SavestateError::RamNotFound);
+            }
+        }
+    }
+
+    Err(SavestateError::RamNotFound)
+}
+
+/// Mesen (Mesen2) のステートセーブファイルから内部 RAM を取り出す。
+///
+/// 形式は Mesen2 の `SaveStateManager::SaveState()` と `Serializer::SaveTo()` による
+/// (数値は全て u32 リトルエンディアン):
+///
+/// ```text
+/// "MSS" エミュレータのバージョン 形式のバージョン コンソール種別
+/// 画面: バッファサイズ 幅 高さ 拡大率 圧縮後サイズ zlib(画面データ)
+/// ROM 名: 長さ 文字列
+/// 本体: 展開後サイズ 圧縮後サイズ zlib(エントリ列)
+/// ```
+///
+/// 本体の各エントリは (NUL 終端のキー, サイズ, データ) で、キーはメンバ名を "." で連結したもの。
+/// 内部 RAM はキーが "internalRam" で終わる 2 KiB のエントリ ("memoryManager.internalRam")。
+pub fn read_mesen_ram(bytes: &[u8]) -> Result<WorkRam, SavestateError> {
+    if !bytes.starts_with(b"MSS") {
+        return Err(SavestateError::UnknownFormat);
+    }
+
+    let mut reader = ByteReader::new(&bytes[3..]);
+
+    // バージョン、コンソール種別。
+    reader.skip(3 * 4)?;
+    // 画面データ。
+    reader.skip(4 * 4)?;
+    let len_video = reader.u32()? as usize;
+    reader.skip(len_video)?;
+    // ROM 名。
+    let len_rom_name = reader.u32()? as usize;
+    reader.skip(len_rom_name)?;
+
+    let len = reader.u32()? as usize;
+    let len_compressed = reader.u32()? as usize;
+    let data = inflate(reader.take(len_compressed)?)?;
+    if data.len() != len {
+        return Err(SavestateError::Truncated);
+    }
+
+    let mut entries = ByteReader::new(&data);
+    while !entries.is_empty() {
+        let key = entries.take_until_nul()?;
+        let size = entries.u32()? as usize;
+        let value = entries.take(size)?;
+
+        if key.ends_with(b"internalRam") {
+            return value.try_into().map_err(|_| SavestateError::RamNotFound);
+        }
+    }
+
+    Err(SavestateError::RamNotFound)
+}
+
+/// ステートセーブから読み取ったゲームの状態。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GameSnapshot {
+    pub rng: Rng,
+    pub money: Money,
+    pub jackpot_small: Money,
+    pub jackpot_big: Money,
+    pub location: u8,
+}
+
+impl GameSnapshot {
+    pub fn from_ram(ram: &WorkRam, map: &RamMap) -> Self {
+        Self {
+            rng: map.rng(ram),
+            money: map.money(ram),
+            jackpot_small: map.jackpot_small(ram),
+            jackpot_big: map.jackpot_big(ram),
+            location: map.location(ram),
+        }
+    }
+
+    /// ステートセーブファイルから読み取る。
+    pub fn from_savestate(bytes: &[u8], map: &RamMap) -> Result<Self, SavestateError> {
+        let ram = read_savestate_ram(bytes)?;
+        Ok(Self::from_ram(&ram, map))
+    }
+}
+
+/// ステートセーブファイルの読み込みエラー。
+#[derive(Debug)]
+pub enum SavestateError {
+    UnknownFormat,
+    Truncated,
+    Decompress(std::io::Error),
+    RamNotFound,
+}
+
+impl std::fmt::Display for SavestateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownFormat => f.write_str("unknown savestate format"),
+            Self::Truncated => f.write_str("savestate is truncated"),
+            Self::Decompress(e) => write!(f, "cannot decompress savestate: {e}"),
+            Self::RamNotFound => f.write_str("work RAM not found in savestate"),
+        }
+    }
+}
+
+impl std::error::Error for SavestateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Decompress(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+fn inflate(compressed: &[u8]) -> Result<Vec<u8>, SavestateError> {
+    let mut data = Vec::new();
+    flate2::read::ZlibDecoder::new(compressed)
+        .read_to_end(&mut data)
+        .map_err(SavestateError::Decompress)?;
+
+    Ok(data)
+}
+
+/// バイト列を先頭から順に読む。
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SavestateError> {
+        if self.bytes.len() < len {
+            return Err(SavestateError::Truncated);
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), SavestateError> {
+        self.take(len).map(|_| ())
+    }
+
+    fn u32(&mut self) -> Result<u32, SavestateError> {
+        self.take(4).map(read_u32_le)
+    }
+
+    /// NUL までを返し、NUL は読み飛ばす。
+    fn take_until_nul(&mut self) -> Result<&'a [u8], SavestateError> {
+        let len = self
+            .bytes
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or(SavestateError::Truncated)?;
+        let head = self.take(len)?;
+        self.skip(1)?;
+        Ok(head)
+    }
+}
+
+fn read_u32_le(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes[..4].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use super::*;
+
+    fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn ram_sample() -> WorkRam {
+        std::array::from_fn(|i| (i * 7 + i / 256) as u8)
+    }
+
+    fn fceux_savestate(ram: &WorkRam, compress: bool) -> Vec<u8> {
+        let chunk = |name: &[u8; 4], data: &[u8]| -> Vec<u8> {
+            let mut buf = name.to_vec();
+            buf.extend((data.len() as u32).to_le_bytes());
+            buf.extend(data);
+            buf
+        };
+        let section = |kind: u8, chunks: &[Vec<u8>]| -> Vec<u8> {
+            let body = chunks.concat();
+            let mut buf = vec![kind];
+            buf.extend((body.len() as u32).to_le_bytes());
+            buf.extend(body);
+            buf
+        };
+
+        let data = [
+            section(
+                1,
+                &[
+                    chunk(b"PC\0\0", &[0x00, 0x80]),
+                    chunk(b"A\0\0\0", &[0x12]),
+                    chunk(b"RAM\0", ram),
+                ],
+            ),
+            section(3, &[chunk(b"NTAR", &[0; 0x800])]),
+        ]
+        .concat();
+
+        let mut buf = b"FCSX".to_vec();
+        buf.extend((data.len() as u32).to_le_bytes());
+        buf.extend(22020_u32.to_le_bytes());
+        if compress {
+            let compressed = deflate(&data);
+            buf.extend((compressed.len() as u32).to_le_bytes());
+            buf.extend(compressed);
+        } else {
+            buf.extend(u32::MAX.to_le_bytes());
+            buf.extend(data);
+        }
+        buf
+    }
+
+    #[test]
+    fn test_read_fceux_ram() {
+        let ram = ram_sample();
+
+        for compress in [false, true] {
+            let bytes = fceux_savestate(&ram, compress);
+            assert_eq!(
+                SavestateFormat::detect(&bytes),
+                Some(SavestateFormat::Fceux)
+            );
+            assert_eq!(read_savestate_ram(&bytes).unwrap(), ram);
+        }
+
+        let bytes = fceux_savestate(&ram, true);
+        assert!(matches!(
+            read_savestate_ram(&bytes[..100]),
+            Err(SavestateError::Truncated | SavestateError::Decompress(_))
+        ));
+    }
+
+    /// Mesen2 の形式 (`read_mesen_ram` のドキュメント参照) に従ったステートセーブ。
+    fn mesen_savestate(ram: &WorkRam) -> Vec<u8> {
+        let entry = |key: &str, data: &[u8]| -> Vec<u8> {
+            let mut buf = key.as_bytes().to_vec();
+            buf.push(0);
+            buf.extend((data.len() as u32).to_le_bytes());
+            buf.extend(data);
+            buf
+        };
+        let data = [
+            entry("cpu.state.a", &[0x12]),
+            entry("cpu.state.pc", &[0x00, 0x80]),
+            // キーの一部が一致するだけのエントリは無視される。
+            entry("ppu.internalRamMirror", &[0; 4]),
+            entry("memoryManager.internalRam", ram),
+            entry("memoryManager.openBus", &[0]),
+        ]
+        .concat();
+
+        let u32_le = |x: u32| x.to_le_bytes();
+        let video = deflate(&[0xFF; 256 * 240 * 2]);
+        let rom_name = b"Caesars Palace (USA).nes";
+        let body = deflate(&data);
+
+        [
+            b"MSS".to_vec(),
+            u32_le(0x0002_0000).to_vec(),
+            u32_le(4).to_vec(),
+            u32_le(1).to_vec(),
+            u32_le(256 * 240 * 2).to_vec(),
+            u32_le(256).to_vec(),
+            u32_le(240).to_vec(),
+            u32_le(100).to_vec(),
+            u32_le(video.len() as u32).to_vec(),
+            video,
+            u32_le(rom_name.len() as u32).to_vec(),
+            rom_name.to_vec(),
+            u32_le(data.len() as u32).to_vec(),
+            u32_le(body.len() as u32).to_vec(),
+            body,
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn test_read_mesen_ram() {
+        let ram = ram_sample();
+        let bytes = mesen_savestate(&ram);
+
+        assert_eq!(
+            SavestateFormat::detect(&bytes),
+            Some(SavestateFormat::Mesen)
+        );
+        assert_eq!(read_savestate_ram(&bytes).unwrap(), ram);
+
+        // 途中で切れたファイルはエラーとなる。
+        for len in [3, 40, bytes.len() - 1] {
+            assert!(matches!(
+                read_savestate_ram(&bytes[..len]),
+                Err(SavestateError::Truncated | SavestateError::Decompress(_))
+            ));
+        }
+    }
+}
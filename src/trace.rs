@@ -0,0 +1,707 @@
+//! エミュレータの記録からの乱数消費の再構成。
+//!
+//! 以下の 2 形式を読み込み、乱数の消費を `Rng` で再生して食い違いを報告する:
+//!
+//! * CPU トレースログ (FCEUX または Mesen)。乱数の状態への STA/STX/STY を拾う。
+//! * フレームごとの RAM ウォッチの CSV。1 行目がアドレス ("frame,$0012,$0300,...")、
+//!   以降の各行がフレーム番号と各アドレスの値。
+//!
+//! 乱数の状態の配置は `RngRamLayout` で与える。
+//! また、1 回のプレイの結果を RAM から読み取り、消費された乱数列から予測される結果と照合できる。
+
+use crate::bandit::{fountains_of_fortune, magnicent_sevens, riches_of_rome};
+use crate::ram::{RamError, RamMap, WorkRam};
+use crate::rng::{Rng, RngIndex, RngRamLayout, TABLE_LEN};
+use crate::trump::{Card, Deck};
+
+/// CPU トレースログ中の、乱数の状態への書き込み。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TraceWrite {
+    pub lineno: usize,
+    pub addr: u16,
+    pub value: u8,
+}
+
+/// CPU トレースログから、`layout` の範囲 (インデックスおよびテーブル) への書き込みを抜き出す。
+///
+/// FCEUX の "STA $0300,X @ $0345 = #$12" と Mesen の "STA $0300,X [$0345] = $12" の両形式に対応する。
+/// 書き込む値は命令実行前のレジスタ値 ("A:12" など) から得る。
+pub fn parse_cpu_trace(s: &str, layout: RngRamLayout) -> Result<Vec<TraceWrite>, ParseTraceError> {
+    let mut writes = vec![];
+
+    for (i, line) in s.lines().enumerate() {
+        let lineno = i + 1;
+        let err = |message: String| ParseTraceError { lineno, message };
+
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some(pos) = tokens
+            .iter()
+            .position(|token| matches!(*token, "STA" | "STX" | "STY"))
+        else {
+            continue;
+        };
+        let reg = &tokens[pos][2..];
+
+        let Some(addr) = store_addr(&tokens[pos + 1..]).map_err(err)? else {
+            continue;
+        };
+        if !is_rng_addr(layout, addr) {
+            continue;
+        }
+
+        let value = tokens
+            .iter()
+            .find_map(|token| token.strip_prefix(reg)?.strip_prefix(':'))
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| err(format!("register {reg} not found")))?;
+
+        writes.push(TraceWrite {
+            lineno,
+            addr,
+            value,
+        });
+    }
+
+    Ok(writes)
+}
+
+/// ストア命令のオペランド以降のトークンから、書き込み先の内部 RAM アドレスを得る。
+/// 内部 RAM 以外への書き込みなら `None`。
+fn store_addr(tokens: &[&str]) -> Result<Option<u16>, String> {
+    let operand = tokens.first().ok_or("missing operand")?;
+
+    // 実効アドレスが併記されていればそれを使う。
+    let effective = tokens.windows(2).find(|w| w[0] == "@").map(|w| w[1]);
+    let effective = effective.or_else(|| {
+        tokens
+            .iter()
+            .find_map(|token| token.strip_prefix('[')?.strip_suffix(']'))
+    });
+
+    let addr = match effective {
+        Some(addr) => addr,
+        None if !operand.contains([',', '(']) => operand,
+        None => return Err(format!("cannot determine effective address: {operand}")),
+    };
+    let addr = addr
+        .strip_prefix('$')
+        .and_then(|hex| u16::from_str_radix(hex, 16).ok())
+        .ok_or_else(|| format!("invalid address: {addr}"))?;
+
+    Ok((addr < 0x2000).then_some(addr & 0x7FF))
+}
+
+fn is_rng_addr(layout: RngRamLayout, addr: u16) -> bool {
+    let table = layout.table_addr()..layout.table_addr() + TABLE_LEN as u16;
+    addr == layout.index_addr() || table.contains(&addr)
+}
+
+/// 乱数の状態を含む RAM ウォッチの 1 行。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RamWatchRow {
+    pub frame: u64,
+    /// 記録されていないアドレスは 0。
+    pub ram: WorkRam,
+}
+
+/// RAM ウォッチの CSV を読み込む。`layout` の範囲が全て記録されていなければエラーを返す。
+pub fn parse_ram_watch_csv(
+    s: &str,
+    layout: RngRamLayout,
+) -> Result<Vec<RamWatchRow>, ParseTraceError> {
+    let mut lines = s
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+
+    let (_, header) = lines.next().ok_or(ParseTraceError {
+        lineno: 0,
+        message: "empty CSV".to_owned(),
+    })?;
+    let addrs = header
+        .split(',')
+        .skip(1)
+        .map(|col| {
+            let col = col.trim();
+            col.strip_prefix('$')
+                .or_else(|| col.strip_prefix("0x"))
+                .and_then(|hex| u16::from_str_radix(hex, 16).ok())
+                .filter(|&addr| addr < 0x800)
+                .ok_or_else(|| ParseTraceError {
+                    lineno: 1,
+                    message: format!("invalid address column: {col:?}"),
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let required = std::iter::once(layout.index_addr())
+        .chain((0..TABLE_LEN as u16).map(|i| layout.table_addr() + i));
+    for addr in required {
+        if !addrs.contains(&addr) {
+            return Err(ParseTraceError {
+                lineno: 1,
+                message: format!("missing address column: ${addr:04X}"),
+            });
+        }
+    }
+
+    lines
+        .map(|(lineno, line)| {
+            let err = |message: String| ParseTraceError { lineno, message };
+
+            let mut cols = line.split(',').map(str::trim);
+            let frame = cols
+                .next()
+                .and_then(|col| col.parse().ok())
+                .ok_or_else(|| err("invalid frame number".to_owned()))?;
+
+            let mut ram = [0; 0x800];
+            let mut count = 0;
+            for (&addr, col) in addrs.iter().zip(cols) {
+                ram[usize::from(addr)] =
+                    parse_byte(col).ok_or_else(|| err(format!("invalid value: {col:?}")))?;
+                count += 1;
+            }
+            if count != addrs.len() {
+                return Err(err(format!("expected {} values", addrs.len())));
+            }
+
+            Ok(RamWatchRow { frame, ram })
+        })
+        .collect()
+}
+
+/// "$12", "0x12" は 16 進、それ以外は 10 進として読む。
+fn parse_byte(s: &str) -> Option<u8> {
+    if let Some(hex) = s.strip_prefix('$').or_else(|| s.strip_prefix("0x")) {
+        u8::from_str_radix(hex, 16).ok()
+    } else {
+        s.parse().ok()
+    }
+}
+
+/// 記録から再構成した 1 回の乱数消費。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TraceDraw {
+    /// CPU トレースなら行番号、RAM ウォッチならフレーム番号。
+    pub source: u64,
+    /// 消費前のインデックス。
+    pub index: RngIndex,
+    pub value: u8,
+}
+
+/// 記録と `Rng` の食い違い。
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TraceMismatch {
+    /// テーブルへの書き込みが `Rng::gen()` の結果と異なる。
+    TableWrite {
+        lineno: usize,
+        expected_slot: usize,
+        expected_value: u8,
+        slot: usize,
+        value: u8,
+    },
+    /// インデックスへの書き込みが `Rng::gen()` 後のインデックスと異なる。
+    IndexWrite {
+        lineno: usize,
+        expected: u8,
+        value: u8,
+    },
+    /// 前のフレームの状態から `draw_max` 回以内の消費で到達できない。
+    Frame { frame: u64 },
+}
+
+impl std::fmt::Display for TraceMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::TableWrite {
+                lineno,
+                expected_slot,
+                expected_value,
+                slot,
+                value,
+            } => write!(
+                f,
+                "line {lineno}: table[{slot}] = 0x{value:02X}, expected table[{expected_slot}] = 0x{expected_value:02X}"
+            ),
+            Self::IndexWrite {
+                lineno,
+                expected,
+                value,
+            } => write!(
+                f,
+                "line {lineno}: index = 0x{value:02X}, expected 0x{expected:02X}"
+            ),
+            Self::Frame { frame } => write!(f, "frame {frame}: RNG state is unreachable"),
+        }
+    }
+}
+
+/// 記録を `Rng` で再生した結果。
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TraceReport {
+    pub draws: Vec<TraceDraw>,
+    pub mismatches: Vec<TraceMismatch>,
+}
+
+impl TraceReport {
+    /// CPU トレースログから得た書き込みを、`rng` を初期状態として再生する。
+    ///
+    /// テーブルへの書き込み 1 回を乱数消費 1 回とみなす。食い違った場合は記録の値に合わせて再生を続ける。
+    ///
+    /// 1 回の消費の中でのテーブルとインデックスへの書き込みの順序はどちらでもよい。
+    /// インデックスへの書き込みが次のインデックスなら、続くテーブルへの書き込みは進める前のインデックスで照合する。
+    pub fn replay_cpu_trace(mut rng: Rng, writes: &[TraceWrite], layout: RngRamLayout) -> Self {
+        let mut report = Self::default();
+        // インデックスがテーブルより先に書き込まれ、消費が完了していない。
+        let mut index_ahead = false;
+
+        for write in writes {
+            if write.addr == layout.index_addr() {
+                let next = rng.index().next().get();
+                let expected = if index_ahead { next } else { rng.index().get() };
+                if write.value == expected {
+                    continue;
+                }
+                if !index_ahead && write.value == next {
+                    index_ahead = true;
+                    continue;
+                }
+
+                report.mismatches.push(TraceMismatch::IndexWrite {
+                    lineno: write.lineno,
+                    expected,
+                    value: write.value,
+                });
+                rng.set_index(write.value);
+                index_ahead = false;
+                continue;
+            }
+            index_ahead = false;
+
+            let slot = usize::from(write.addr - layout.table_addr());
+            let index = rng.index();
+            let expected_slot = index.slot_x();
+
+            let mut rng_next = rng.clone();
+            let expected_value = rng_next.gen();
+
+            if slot == expected_slot && write.value == expected_value {
+                rng = rng_next;
+                report.draws.push(TraceDraw {
+                    source: write.lineno as u64,
+                    index,
+                    value: write.value,
+                });
+            } else {
+                report.mismatches.push(TraceMismatch::TableWrite {
+                    lineno: write.lineno,
+                    expected_slot,
+                    expected_value,
+                    slot,
+                    value: write.value,
+                });
+                let mut table = *rng.table();
+                table[slot] = write.value;
+                rng = Rng::from_raw(rng.index(), &table);
+            }
+        }
+
+        report
+    }
+
+    /// RAM ウォッチの各フレーム間の乱数消費を再構成する。
+    ///
+    /// 各フレーム間で高々 `draw_max` 回の消費を仮定する。到達できないフレームでは記録の状態から再生し直す。
+    pub fn replay_ram_watch(rows: &[RamWatchRow], layout: RngRamLayout, draw_max: usize) -> Self {
+        let mut report = Self::default();
+
+        let Some(first) = rows.first() else {
+            return report;
+        };
+        let mut rng = Rng::from_ram(&first.ram, layout);
+
+        for row in &rows[1..] {
+            let target = Rng::from_ram(&row.ram, layout);
+
+            let mut probe = rng.clone();
+            let mut draws = vec![];
+            let mut reached = probe == target;
+            while !reached && draws.len() < draw_max {
+                let index = probe.index();
+                let value = probe.gen();
+                draws.push(TraceDraw {
+                    source: row.frame,
+                    index,
+                    value,
+                });
+                reached = probe == target;
+            }
+
+            if reached {
+                report.draws.extend(draws);
+            } else {
+                report
+                    .mismatches
+                    .push(TraceMismatch::Frame { frame: row.frame });
+            }
+            rng = target;
+        }
+
+        report
+    }
+
+    /// 再構成した乱数列。
+    pub fn values(&self) -> Vec<u8> {
+        self.draws.iter().map(|draw| draw.value).collect()
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+impl std::fmt::Display for TraceReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} draws, {} mismatches",
+            self.draws.len(),
+            self.mismatches.len()
+        )?;
+        for mismatch in &self.mismatches {
+            writeln!(f, "{mismatch}")?;
+        }
+        Ok(())
+    }
+}
+
+/// 結果を照合するゲーム。
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum TraceGame {
+    Poker,
+    Fof,
+    Ms,
+    Ror,
+}
+
+impl TraceGame {
+    pub const fn all() -> [Self; 4] {
+        [Self::Poker, Self::Fof, Self::Ms, Self::Ror]
+    }
+
+    /// `RamMap` の固有の値の名前の接頭辞。
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Poker => "poker",
+            Self::Fof => "fof",
+            Self::Ms => "ms",
+            Self::Ror => "ror",
+        }
+    }
+
+    /// 1 回のプレイで消費する乱数の個数。RAM 上の結果の個数と同じ。
+    pub const fn draw_count(self) -> usize {
+        match self {
+            Self::Poker => 5,
+            Self::Fof => 4,
+            Self::Ms | Self::Ror => 3,
+        }
+    }
+
+    /// RAM 上の結果の `i` 番目の値の名前 ("poker.card0", "fof.reel0" など)。
+    pub fn field_name(self, i: usize) -> String {
+        let item = if self == Self::Poker { "card" } else { "reel" };
+        format!("{}.{item}{i}", self.name())
+    }
+
+    /// 乱数列 `rs` から予測される結果。
+    ///
+    /// ポーカーは `Deck::deal()` で配られたカードの内部値、スロットマシンは各リールの停止位置。
+    /// `rs` の長さが `draw_count()` と異なるならパニックする。
+    pub fn predict(self, rs: &[u8]) -> Vec<u8> {
+        assert_eq!(
+            rs.len(),
+            self.draw_count(),
+            "{}: wrong number of draws",
+            self.name()
+        );
+
+        match self {
+            Self::Poker => {
+                let mut deck = Deck::new();
+                rs.iter().map(|&r| deck.deal(r).inner()).collect()
+            }
+            Self::Fof => fountains_of_fortune::randoms_to_reels(
+                &fountains_of_fortune::REEL_TABLES,
+                rs.try_into().unwrap(),
+            )
+            .to_vec(),
+            Self::Ms => magnicent_sevens::randoms_to_reels(
+                &magnicent_sevens::REEL_TABLES,
+                rs.try_into().unwrap(),
+            )
+            .to_vec(),
+            Self::Ror => riches_of_rome::randoms_to_reels(
+                &riches_of_rome::REEL_BASES,
+                rs.try_into().unwrap(),
+            )
+            .to_vec(),
+        }
+    }
+
+    /// RAM 上の結果を `map` の固有の値 (`field_name()`) から読み取る。
+    pub fn extract(self, map: &RamMap, ram: &WorkRam) -> Result<Vec<u8>, RamError> {
+        (0..self.draw_count())
+            .map(|i| {
+                let name = self.field_name(i);
+                let value = map.field(&name, ram)?;
+                u8::try_from(value).map_err(|_| RamError::ValueOutOfRange {
+                    value,
+                    max: u32::from(u8::MAX),
+                })
+            })
+            .collect()
+    }
+
+    fn format_value(self, value: u8) -> String {
+        match self {
+            Self::Poker => Card::from_inner(value).map_or_else(
+                || format!("0x{value:02X} (invalid)"),
+                |card| card.to_string(),
+            ),
+            _ => format!("0x{value:02X}"),
+        }
+    }
+}
+
+/// 1 回のプレイの、RAM 上の結果と乱数列から予測される結果の比較。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ResultCheck {
+    pub game: TraceGame,
+    pub expected: Vec<u8>,
+    pub actual: Vec<u8>,
+}
+
+impl ResultCheck {
+    /// プレイで消費された乱数列 `rs` と、プレイ直後の RAM `ram` 上の結果を比較する。
+    pub fn new(game: TraceGame, rs: &[u8], map: &RamMap, ram: &WorkRam) -> Result<Self, RamError> {
+        Ok(Self {
+            game,
+            expected: game.predict(rs),
+            actual: game.extract(map, ram)?,
+        })
+    }
+
+    pub fn is_match(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+impl std::fmt::Display for ResultCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_match() {
+            return writeln!(f, "{}: result matches", self.game.name());
+        }
+
+        for (i, (&expected, &actual)) in self.expected.iter().zip(&self.actual).enumerate() {
+            if expected != actual {
+                writeln!(
+                    f,
+                    "{}: expected {}, found {}",
+                    self.game.field_name(i),
+                    self.game.format_value(expected),
+                    self.game.format_value(actual)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// トレースログの解析エラー。
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseTraceError {
+    /// 0 ならファイル全体に関するエラー。
+    pub lineno: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseTraceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.lineno == 0 {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "line {}: {}", self.lineno, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ParseTraceError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout() -> RngRamLayout {
+        RngRamLayout::new(0x10, 0x300).unwrap()
+    }
+
+    /// `rng` から `count` 回消費したときの CPU トレースログ (乱数ルーチンのストア部分のみ) を作る。
+    /// `index_first` ならインデックスをテーブルより先に書き込む。
+    fn synthetic_trace(rng: &Rng, count: usize, mesen: bool, index_first: bool) -> String {
+        let mut rng = rng.clone();
+        let mut lines = vec!["C000  A5 10     LDA $10 = #$00  A:00 X:00 Y:00 S:FD".to_owned()];
+
+        for _ in 0..count {
+            let x = rng.index().slot_x();
+            let r = rng.gen();
+            let addr = 0x300 + x;
+            let index = rng.index();
+            let index_line =
+                format!("$C028:86 10     STX $10 = #$00  A:{r:02X} X:{index:02X} Y:00");
+            if index_first {
+                lines.push(index_line.clone());
+            }
+            lines.push(if mesen {
+                format!("C01A  9D 00 03  STA $0300,X [${addr:04X}] = $00  A:{r:02X} X:{x:02X} Y:00")
+            } else {
+                format!(
+                    "$C01A:9D 00 03  STA $0300,X @ ${addr:04X} = #$00  A:{r:02X} X:{x:02X} Y:00"
+                )
+            });
+            if !index_first {
+                lines.push(index_line);
+            }
+        }
+
+        lines.join("\n")
+    }
+
+    #[test]
+    fn test_replay_cpu_trace() {
+        let rng = Rng::with_index(240);
+        let rs: Vec<u8> = {
+            let mut rng = rng.clone();
+            (0..30).map(|_| rng.gen()).collect()
+        };
+
+        for mesen in [false, true] {
+            for index_first in [false, true] {
+                let trace = synthetic_trace(&rng, 30, mesen, index_first);
+                let writes = parse_cpu_trace(&trace, layout()).unwrap();
+                assert_eq!(writes.len(), 60);
+
+                let report = TraceReport::replay_cpu_trace(rng.clone(), &writes, layout());
+                assert!(report.is_consistent(), "{report}");
+                assert_eq!(report.values(), rs);
+            }
+        }
+
+        // 初期状態がずれていれば検出できる。
+        for index_first in [false, true] {
+            let trace = synthetic_trace(&rng, 30, false, index_first);
+            let writes = parse_cpu_trace(&trace, layout()).unwrap();
+            let report = TraceReport::replay_cpu_trace(Rng::with_index(241), &writes, layout());
+            assert!(!report.is_consistent());
+        }
+
+        let err = parse_cpu_trace("$C000:9D 00 03  STA $0300,X  A:00", layout()).unwrap_err();
+        assert_eq!(err.lineno, 1);
+    }
+
+    #[test]
+    fn test_replay_ram_watch() {
+        let layout = layout();
+        let mut rng = Rng::with_index(100);
+
+        let mut csv = String::from("frame,$0010");
+        for i in 0..TABLE_LEN {
+            csv.push_str(&format!(",$0{:03X}", 0x300 + i));
+        }
+        csv.push('\n');
+
+        let mut rs = vec![];
+        for (frame, count) in [0, 3, 0, 4, 1].into_iter().enumerate() {
+            rs.extend((0..count).map(|_| rng.gen()));
+
+            csv.push_str(&format!("{frame},{}", rng.index()));
+            for value in rng.table() {
+                csv.push_str(&format!(",{value}"));
+            }
+            csv.push('\n');
+        }
+
+        let rows = parse_ram_watch_csv(&csv, layout).unwrap();
+        assert_eq!(rows.len(), 5);
+
+        let report = TraceReport::replay_ram_watch(&rows, layout, 10);
+        assert!(report.is_consistent(), "{report}");
+        assert_eq!(report.values(), rs);
+        assert_eq!(report.draws[3].source, 3);
+
+        let report = TraceReport::replay_ram_watch(&rows, layout, 3);
+        assert_eq!(report.mismatches, [TraceMismatch::Frame { frame: 3 }]);
+
+        assert!(parse_ram_watch_csv("frame,$0010\n0,1\n", layout).is_err());
+    }
+
+    #[test]
+    fn test_result_check() {
+        let mut text = "rng_index = 0x10\nrng_table = 0x300\nmoney = 0x20:3:bcd-le\n\
+                        jackpot_small = 0x24:3:bcd-le\njackpot_big = 0x28:3:bcd-le\n\
+                        location = 0x30\n"
+            .to_owned();
+        for (i, game) in TraceGame::all().into_iter().enumerate() {
+            for j in 0..game.draw_count() {
+                let addr = 0x40 + 8 * i + j;
+                text.push_str(&format!("{} = 0x{addr:X}:1:bin-le\n", game.field_name(j)));
+            }
+        }
+        let map: RamMap = text.parse().unwrap();
+
+        let mut rng = Rng::with_index(33);
+        let rs: Vec<u8> = (0..5).map(|_| rng.gen()).collect();
+
+        let mut ram = [0; 0x800];
+        for game in TraceGame::all() {
+            let rs = &rs[..game.draw_count()];
+            for (j, value) in game.predict(rs).into_iter().enumerate() {
+                map.set_field(&game.field_name(j), &mut ram, value.into())
+                    .unwrap();
+            }
+            let check = ResultCheck::new(game, rs, &map, &ram).unwrap();
+            assert!(check.is_match(), "{check}");
+        }
+
+        // ポーカーの結果は `Deck::deal()` で配られたカードたち。
+        let mut deck = Deck::new();
+        let cards: Vec<_> = rs.iter().map(|&r| deck.deal(r).inner()).collect();
+        assert_eq!(TraceGame::Poker.extract(&map, &ram), Ok(cards.clone()));
+
+        // 食い違いは値ごとに報告される。
+        map.set_field("poker.card2", &mut ram, u32::from(cards[2] ^ 1))
+            .unwrap();
+        let check = ResultCheck::new(TraceGame::Poker, &rs, &map, &ram).unwrap();
+        assert_eq!(
+            check.to_string(),
+            format!(
+                "poker.card2: expected {}, found {}\n",
+                Card::from_inner(cards[2]).unwrap(),
+                Card::from_inner(cards[2] ^ 1).unwrap()
+            )
+        );
+
+        ram[0x40 + 8 + 1] ^= 1;
+        let check = ResultCheck::new(TraceGame::Fof, &rs[..4], &map, &ram).unwrap();
+        assert_eq!(
+            check.to_string(),
+            format!(
+                "fof.reel1: expected 0x{:02X}, found 0x{:02X}\n",
+                check.expected[1],
+                check.expected[1] ^ 1
+            )
+        );
+    }
+}
@@ -1,5 +1,3 @@
-use arrayvec::ArrayVec;
-
 #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
 #[repr(u8)]
 pub enum CardSuit {
@@ -34,6 +32,16 @@ impl CardSuit {
     pub const fn all() -> [Self; 4] {
         [Self::Heart, Self::Diamond, Self::Spade, Self::Club]
     }
+
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            'H' | 'h' | '♥' | '♡' => Some(Self::Heart),
+            'D' | 'd' | '♦' | '♢' => Some(Self::Diamond),
+            'S' | 's' | '♠' | '♤' => Some(Self::Spade),
+            'C' | 'c' | '♣' | '♧' => Some(Self::Club),
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for CardSuit {
@@ -48,6 +56,19 @@ impl std::fmt::Display for CardSuit {
     }
 }
 
+impl std::str::FromStr for CardSuit {
+    type Err = ParseCardError;
+
+    /// "H", "d", "♠" などをパースする。大文字小文字は区別しない。
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Self::from_char(c).ok_or_else(|| ParseCardError::Suit(s.to_owned())),
+            _ => Err(ParseCardError::Suit(s.to_owned())),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
 pub struct CardRank(u8);
 
@@ -119,6 +140,32 @@ impl std::fmt::Display for CardRank {
     }
 }
 
+impl std::str::FromStr for CardRank {
+    type Err = ParseCardError;
+
+    /// "A", "10", "T", "k" などをパースする。大文字小文字は区別しない。
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let inner = match s.to_ascii_uppercase().as_str() {
+            "A" => 0,
+            "2" => 1,
+            "3" => 2,
+            "4" => 3,
+            "5" => 4,
+            "6" => 5,
+            "7" => 6,
+            "8" => 7,
+            "9" => 8,
+            "10" | "T" => 9,
+            "J" => 10,
+            "Q" => 11,
+            "K" => 12,
+            _ => return Err(ParseCardError::Rank(s.to_owned())),
+        };
+
+        Ok(unsafe { Self::from_inner_unchecked(inner) })
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
 pub struct Card(u8);
 
@@ -140,6 +187,10 @@ impl Card {
         Self(inner)
     }
 
+    pub const fn inner(self) -> u8 {
+        self.0
+    }
+
     pub const fn from_suit_rank(suit: CardSuit, rank: CardRank) -> Self {
         let inner = 13 * suit.inner() + rank.inner();
 
@@ -228,36 +279,613 @@ impl std::fmt::Display for Card {
     }
 }
 
-type DeckCards = ArrayVec<Card, 52>;
+impl std::str::FromStr for Card {
+    type Err = ParseCardError;
+
+    /// `Display` と同じ "スート, ランク" 順 ("HA", "S10") と、
+    /// "ランク, スート" 順 ("Ah", "10s", "T♠") の両方を受け付ける。
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseCardError::Card(s.to_owned());
+
+        let first = s.chars().next().ok_or_else(invalid)?;
+        let last = s.chars().next_back().ok_or_else(invalid)?;
+
+        // ランクの表記はスートの表記と重ならないので、両端を見ればどちらの順かわかる。
+        let (suit, rank) = if let Some(suit) = CardSuit::from_char(first) {
+            (suit, &s[first.len_utf8()..])
+        } else if let Some(suit) = CardSuit::from_char(last) {
+            (suit, &s[..s.len() - last.len_utf8()])
+        } else {
+            return Err(invalid());
+        };
+
+        let rank = rank.parse::<CardRank>().map_err(|_| invalid())?;
+
+        Ok(Self::from_suit_rank(suit, rank))
+    }
+}
+
+/// 空白区切りのカード列 ("HA S10 CK" など) をパースする。
+pub fn parse_hand(s: &str) -> Result<Vec<Card>, ParseCardError> {
+    s.split_whitespace().map(str::parse).collect()
+}
 
+/// カード関連の文字列パースエラー。
 #[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseCardError {
+    Suit(String),
+    Rank(String),
+    Card(String),
+}
+
+impl std::fmt::Display for ParseCardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Suit(s) => write!(f, "invalid card suit: {s:?}"),
+            Self::Rank(s) => write!(f, "invalid card rank: {s:?}"),
+            Self::Card(s) => write!(f, "invalid card: {s:?}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseCardError {}
+
+/// カードの集合。内部値 `i` のカードを bit `i` で表す。
+///
+/// 反復は `Card` の順序 (内部値の昇順) で行われる。
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct CardSet(u64);
+
+impl CardSet {
+    const MASK_ALL: u64 = (1 << 52) - 1;
+    const MASK_RANKS: u64 = (1 << 13) - 1;
+    const MASK_RANK_0: u64 = 1 | (1 << 13) | (1 << 26) | (1 << 39);
+
+    /// 空集合を返す。
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// 全 52 枚からなる集合を返す。
+    pub const fn full() -> Self {
+        Self(Self::MASK_ALL)
+    }
+
+    pub const fn from_inner(inner: u64) -> Option<Self> {
+        if inner & !Self::MASK_ALL == 0 {
+            Some(Self(inner))
+        } else {
+            None
+        }
+    }
+
+    pub const fn inner(self) -> u64 {
+        self.0
+    }
+
+    pub const fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn contains(self, card: Card) -> bool {
+        (self.0 & Self::bit(card)) != 0
+    }
+
+    /// カードを追加し、新たに追加されたかどうかを返す。
+    pub fn insert(&mut self, card: Card) -> bool {
+        let added = !self.contains(card);
+        self.0 |= Self::bit(card);
+        added
+    }
+
+    /// カードを削除し、削除前に含まれていたかどうかを返す。
+    pub fn remove(&mut self, card: Card) -> bool {
+        let removed = self.contains(card);
+        self.0 &= !Self::bit(card);
+        removed
+    }
+
+    /// 指定したスートのカードのみからなる部分集合を返す。
+    pub const fn of_suit(self, suit: CardSuit) -> Self {
+        Self(self.0 & (Self::MASK_RANKS << Self::suit_shift(suit)))
+    }
+
+    /// 指定したランクのカードのみからなる部分集合を返す。
+    pub const fn of_rank(self, rank: CardRank) -> Self {
+        Self(self.0 & (Self::MASK_RANK_0 << rank.inner()))
+    }
+
+    /// 指定したスートのカードたちのランクを 13 bit のマスクとして返す。
+    pub const fn rank_mask(self, suit: CardSuit) -> u16 {
+        ((self.0 >> Self::suit_shift(suit)) & Self::MASK_RANKS) as u16
+    }
+
+    /// スートを問わず、含まれるカードたちのランクを 13 bit のマスクとして返す。
+    pub const fn rank_mask_any(self) -> u16 {
+        let x = self.0;
+        let mask = x | (x >> 13) | (x >> 26) | (x >> 39);
+        (mask & Self::MASK_RANKS) as u16
+    }
+
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub const fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub const fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    pub const fn symmetric_difference(self, other: Self) -> Self {
+        Self(self.0 ^ other.0)
+    }
+
+    /// 補集合 (全 52 枚に対する) を返す。
+    pub const fn complement(self) -> Self {
+        Self(!self.0 & Self::MASK_ALL)
+    }
+
+    pub const fn is_subset(self, other: Self) -> bool {
+        (self.0 & !other.0) == 0
+    }
+
+    pub fn iter(self) -> CardSetIter {
+        CardSetIter(self.0)
+    }
+
+    /// `Card` の順序で `n` 番目 (0-based) の要素を返す。
+    pub const fn nth(self, n: usize) -> Option<Card> {
+        if n >= self.len() {
+            return None;
+        }
+
+        // 32, 16, 8 bit 単位で読み飛ばしてから、残りを 1 bit ずつ見る。
+        let mut x = self.0;
+        let mut n = n as u32;
+        let mut base = 0;
+        let mut width = 32;
+        while width >= 8 {
+            let mask = (1 << width) - 1;
+            let count = (x & mask).count_ones();
+            if n >= count {
+                n -= count;
+                x >>= width;
+                base += width;
+            }
+            width /= 2;
+        }
+        while n > 0 {
+            x &= x - 1;
+            n -= 1;
+        }
+        let inner = (base + x.trailing_zeros()) as u8;
+
+        Some(unsafe { Card::from_inner_unchecked(inner) })
+    }
+
+    const fn bit(card: Card) -> u64 {
+        1 << card.inner()
+    }
+
+    const fn suit_shift(suit: CardSuit) -> u32 {
+        13 * suit.inner() as u32
+    }
+}
+
+impl std::ops::BitOr for CardSet {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        self.union(rhs)
+    }
+}
+
+impl std::ops::BitAnd for CardSet {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        self.intersection(rhs)
+    }
+}
+
+impl std::ops::BitXor for CardSet {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self {
+        self.symmetric_difference(rhs)
+    }
+}
+
+impl std::ops::Sub for CardSet {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        self.difference(rhs)
+    }
+}
+
+impl std::ops::Not for CardSet {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        self.complement()
+    }
+}
+
+impl std::ops::BitOrAssign for CardSet {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = *self | rhs;
+    }
+}
+
+impl std::ops::BitAndAssign for CardSet {
+    fn bitand_assign(&mut self, rhs: Self) {
+        *self = *self & rhs;
+    }
+}
+
+impl std::ops::BitXorAssign for CardSet {
+    fn bitxor_assign(&mut self, rhs: Self) {
+        *self = *self ^ rhs;
+    }
+}
+
+impl std::ops::SubAssign for CardSet {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl FromIterator<Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item = Card>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<Card> for CardSet {
+    fn extend<I: IntoIterator<Item = Card>>(&mut self, iter: I) {
+        for card in iter {
+            self.insert(card);
+        }
+    }
+}
+
+impl IntoIterator for CardSet {
+    type Item = Card;
+    type IntoIter = CardSetIter;
+
+    fn into_iter(self) -> CardSetIter {
+        self.iter()
+    }
+}
+
+impl std::fmt::Display for CardSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, card) in self.iter().enumerate() {
+            if i != 0 {
+                f.write_str(" ")?;
+            }
+            card.fmt(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// `CardSet` の要素を `Card` の順序で列挙する反復子。
+#[derive(Clone, Debug)]
+pub struct CardSetIter(u64);
+
+impl Iterator for CardSetIter {
+    type Item = Card;
+
+    fn next(&mut self) -> Option<Card> {
+        if self.0 == 0 {
+            return None;
+        }
+
+        let inner = self.0.trailing_zeros() as u8;
+        self.0 &= self.0 - 1;
+
+        Some(unsafe { Card::from_inner_unchecked(inner) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for CardSetIter {
+    fn next_back(&mut self) -> Option<Card> {
+        if self.0 == 0 {
+            return None;
+        }
+
+        let inner = 63 - self.0.leading_zeros() as u8;
+        self.0 &= !(1 << inner);
+
+        Some(unsafe { Card::from_inner_unchecked(inner) })
+    }
+}
+
+impl ExactSizeIterator for CardSetIter {}
+impl std::iter::FusedIterator for CardSetIter {}
+
+/// 山札。
+///
+/// 残っているカードは常に `Card` の順序で並んでいるとみなせるので、集合として持つ。
+///
+/// 等価性は残っているカードの集合のみで判定する (以降に引かれるカードはそれだけで決まるため)。
+/// `undeal` の検証用の情報は比較しない。
+#[derive(Clone, Copy, Debug)]
 pub struct Deck {
-    cards: DeckCards,
+    cards: CardSet,
+    /// 山札が空になって全 52 枚に戻った回数。`undeal` で戻せる範囲の検証に使う。
+    refill_count: u32,
+    /// 直前に `deal` で引いたカード。`undeal` した後は不明なので `None`。
+    last_dealt: Option<Card>,
 }
 
 impl Default for Deck {
     fn default() -> Self {
         Self {
-            cards: Self::cards_default(),
+            cards: CardSet::full(),
+            refill_count: 0,
+            last_dealt: None,
         }
     }
 }
 
+impl PartialEq for Deck {
+    fn eq(&self, other: &Self) -> bool {
+        self.cards == other.cards
+    }
+}
+
+impl Eq for Deck {}
+
 impl Deck {
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// 乱数 `r` を用いてカードを 1 枚引く。
+    ///
+    /// 山札が空になったら全 52 枚に戻す。
+    /// (原作では次に引く際に戻すが、引かれるカードは変わらない。こうしておくと `undeal` が一意に定まる)
     pub fn deal(&mut self, r: u8) -> Card {
+        let idx = usize::from(r) % self.cards.len();
+        let card = self.cards.nth(idx).unwrap();
+        self.cards.remove(card);
+        self.last_dealt = Some(card);
+
         if self.cards.is_empty() {
-            self.cards = Self::cards_default();
+            self.cards = CardSet::full();
+            self.refill_count += 1;
         }
 
-        let idx = usize::from(r) % self.cards.len();
-        self.cards.remove(idx)
+        card
+    }
+
+    /// 直前に `deal` で引いたカードを山札に戻し、`deal` 前の状態に復元する。
+    ///
+    /// 山札は常に `Card` の順序で並んでいるので、戻す位置を指定する必要はない。
+    /// 複数枚戻す場合は引いたのと逆の順 (LIFO) で呼ぶこと。
+    /// 順序を誤ると山札が壊れるが、検証できるのは直前の `deal` の直後の 1 回のみ (debug ビルドのみ)。
+    ///
+    /// # Panics
+    ///
+    /// `card` が山札に残っている (このデッキから引かれたカードでない) 場合。
+    pub fn undeal(&mut self, card: Card) {
+        if let Some(last) = self.last_dealt.take() {
+            debug_assert_eq!(card, last, "undeal: {card} is not the last dealt card");
+        }
+
+        if self.cards.contains(card) {
+            // 最後の 1 枚を引いて全 52 枚に戻ったケース。
+            assert!(
+                self.cards == CardSet::full() && self.refill_count > 0,
+                "undeal: {card} has not been dealt from this deck"
+            );
+            self.cards = CardSet::new();
+            self.refill_count -= 1;
+        }
+
+        self.cards.insert(card);
+    }
+
+    /// 残っているカードの集合を返す。
+    pub fn cards(&self) -> CardSet {
+        self.cards
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Rng;
+
+    use super::*;
+
+    #[test]
+    fn test_card_parse_display_roundtrip() {
+        for card in Card::all() {
+            assert_eq!(card.to_string().parse::<Card>(), Ok(card));
+        }
+    }
+
+    #[test]
+    fn test_card_parse_alternatives() {
+        let spade_10 = Card::from_suit_rank(CardSuit::Spade, CardRank::VALUE_9);
+        let heart_a = Card::from_suit_rank(CardSuit::Heart, CardRank::VALUE_0);
+
+        for s in ["S10", "10s", "T♠", "s10", "♤T", "st"] {
+            assert_eq!(s.parse::<Card>(), Ok(spade_10), "{s}");
+        }
+        assert_eq!("Ah".parse::<Card>(), Ok(heart_a));
+
+        for s in ["", "H", "10", "X10", "H1", "H11", "HAS", "AX"] {
+            assert_eq!(
+                s.parse::<Card>(),
+                Err(ParseCardError::Card(s.to_owned())),
+                "{s}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_hand() {
+        let hand = parse_hand(" HA  S10\tCK ").unwrap();
+        assert_eq!(
+            hand,
+            [
+                Card::from_suit_rank(CardSuit::Heart, CardRank::VALUE_0),
+                Card::from_suit_rank(CardSuit::Spade, CardRank::VALUE_9),
+                Card::from_suit_rank(CardSuit::Club, CardRank::VALUE_12),
+            ]
+        );
+
+        assert!(parse_hand("HA ZZ").is_err());
+    }
+
+    #[test]
+    fn test_card_set() {
+        let hand: CardSet = parse_hand("HA H10 S10 CK D2")
+            .unwrap()
+            .into_iter()
+            .collect();
+
+        assert_eq!(hand.len(), 5);
+        assert_eq!(hand.to_string(), "HA H10 D2 S10 CK");
+        assert!(hand.contains("S10".parse().unwrap()));
+        assert!(!hand.contains("S9".parse().unwrap()));
+
+        assert_eq!(hand.rank_mask(CardSuit::Heart), (1 << 0) | (1 << 9));
+        assert_eq!(hand.rank_mask(CardSuit::Club), 1 << 12);
+        assert_eq!(
+            hand.rank_mask_any(),
+            (1 << 0) | (1 << 1) | (1 << 9) | (1 << 12)
+        );
+        assert_eq!(hand.of_suit(CardSuit::Spade).to_string(), "S10");
+        assert_eq!(hand.of_rank(CardRank::VALUE_9).to_string(), "H10 S10");
+
+        let all = CardSet::full();
+        assert_eq!(all.len(), 52);
+        assert!(all.iter().eq(Card::all()));
+        assert!(all.iter().rev().eq(Card::all().into_iter().rev()));
+        assert_eq!(!hand | hand, all);
+        assert_eq!((all - hand).len(), 47);
+        assert!(hand.is_subset(all));
+    }
+
+    /// 旧実装 (`Vec` から `remove` する) による山札。
+    #[derive(Clone, Debug)]
+    struct DeckNaive(Vec<Card>);
+
+    impl DeckNaive {
+        fn new() -> Self {
+            Self(Card::all().to_vec())
+        }
+
+        fn deal(&mut self, r: u8) -> Card {
+            if self.0.is_empty() {
+                self.0 = Card::all().to_vec();
+            }
+
+            let idx = usize::from(r) % self.0.len();
+            self.0.remove(idx)
+        }
+    }
+
+    #[test]
+    fn test_card_set_nth() {
+        let set: CardSet = parse_hand("HA H10 D2 S10 CK")
+            .unwrap()
+            .into_iter()
+            .collect();
+        for (i, card) in set.iter().enumerate() {
+            assert_eq!(set.nth(i), Some(card));
+        }
+        assert_eq!(set.nth(5), None);
+
+        for (i, card) in Card::all().into_iter().enumerate() {
+            assert_eq!(CardSet::full().nth(i), Some(card));
+        }
+    }
+
+    #[test]
+    fn test_deck_deal_matches_naive() {
+        // 全ての山札サイズ (周回を含む) において、全ての r に対し同じカードを引くことを確認する。
+        for index in [0x00, 0x5A, 0xFF] {
+            let mut rng = Rng::with_index(index);
+            let mut deck = Deck::new();
+            let mut deck_naive = DeckNaive::new();
+
+            for _ in 0..120 {
+                for r in 0..=0xFF {
+                    let mut deck_probe = deck;
+                    let mut deck_naive_probe = deck_naive.clone();
+                    assert_eq!(deck_probe.deal(r), deck_naive_probe.deal(r));
+                }
+
+                let r = rng.gen();
+                assert_eq!(deck.deal(r), deck_naive.deal(r));
+                // 空の山札は全 52 枚の山札と等価。
+                let cards_naive = if deck_naive.0.is_empty() {
+                    CardSet::full()
+                } else {
+                    deck_naive.0.iter().copied().collect()
+                };
+                assert_eq!(deck.cards(), cards_naive);
+            }
+        }
+    }
+
+    #[test]
+    fn test_deck_undeal() {
+        for index in 0..=0xFF {
+            let mut rng = Rng::with_index(index);
+            let mut deck = Deck::new();
+
+            // 周回をまたぐように引く。
+            for _ in 0..40 {
+                deck.deal(rng.gen());
+            }
+
+            let deck_orig = deck;
+            let cards: Vec<_> = (0..60).map(|_| deck.deal(rng.gen())).collect();
+            for &card in cards.iter().rev() {
+                deck.undeal(card);
+            }
+
+            assert_eq!(deck, deck_orig);
+            assert_eq!(deck.refill_count, deck_orig.refill_count);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "has not been dealt")]
+    fn test_deck_undeal_not_dealt() {
+        let card = "AS".parse().unwrap();
+        Deck::new().undeal(card);
     }
 
-    fn cards_default() -> DeckCards {
-        DeckCards::from(Card::all())
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "is not the last dealt card")]
+    fn test_deck_undeal_not_last() {
+        let mut deck = Deck::new();
+        let first = deck.deal(0);
+        deck.deal(0);
+        deck.undeal(first);
     }
 }